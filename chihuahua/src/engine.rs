@@ -0,0 +1,104 @@
+//! Pluggable WASM execution engines
+//!
+//! Historically every provisioned program ran through the single hardwired
+//! `chihuahua::hcall` interpreter path.  This module abstracts program execution
+//! behind the [`ExecutionEngine`] trait, which a wasmtime-backed engine
+//! implements.  [`ExecutionStrategy`] names either that engine or the in-tree
+//! interpreter; the interpreter does not implement the trait and keeps its own
+//! `hcall` path.
+//!
+//! The wasmtime engine accepts a [`ResourceBudget`]: a fuel/instruction budget and
+//! an optional memory cap.  Running out of fuel aborts execution and surfaces
+//! [`EngineError::ResourceExhausted`]; a `memory.grow` past the cap is refused
+//! without aborting execution.  The interpreter path takes no budget.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+use crate::hcall::MachineState;
+
+/// Which execution backend services a computation.  The caller chooses it and
+/// builds the backend with [`ExecutionStrategy::new_engine`]; it is not read from
+/// the policy JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionStrategy {
+    /// The in-tree `chihuahua::hcall` interpreter.
+    Interpreter,
+    /// The wasmtime-backed engine.
+    Wasmtime,
+}
+
+impl Default for ExecutionStrategy {
+    fn default() -> Self {
+        ExecutionStrategy::Interpreter
+    }
+}
+
+/// Deterministic resource bounds applied to a single execution.
+#[derive(Clone, Copy, Debug)]
+pub struct ResourceBudget {
+    /// Maximum fuel (instructions) the program may consume before it is aborted.
+    pub fuel: u64,
+    /// Optional linear-memory cap, in bytes.
+    pub memory_cap: Option<usize>,
+}
+
+impl Default for ResourceBudget {
+    fn default() -> Self {
+        // Unbounded: no fuel limit and no memory cap.  No budget is derived from
+        // the policy.
+        ResourceBudget {
+            fuel: u64::MAX,
+            memory_cap: None,
+        }
+    }
+}
+
+/// Errors an execution engine can surface.
+#[derive(Debug)]
+pub enum EngineError {
+    /// The program was rejected at load time.
+    InvalidProgram(String),
+    /// The program exhausted its fuel budget and was aborted.
+    ResourceExhausted,
+    /// A trap or host-call error during execution.
+    ExecutionTrap(String),
+}
+
+impl ExecutionStrategy {
+    /// Instantiate the execution backend this strategy selects.
+    ///
+    /// [`ExecutionStrategy::Wasmtime`] builds and returns a boxed
+    /// [`WasmtimeEngine`](crate::engine_wasmtime::WasmtimeEngine) so the wasmtime
+    /// path is actually constructed from the selected strategy rather than left as
+    /// dead code.  [`ExecutionStrategy::Interpreter`] returns `None`: the in-tree
+    /// `chihuahua::hcall` interpreter is driven through its own hardwired path and
+    /// does not go through this boxed surface, so there is nothing to construct.
+    pub fn new_engine(self) -> Result<Option<Box<dyn ExecutionEngine>>, EngineError> {
+        match self {
+            ExecutionStrategy::Interpreter => Ok(None),
+            ExecutionStrategy::Wasmtime => Ok(Some(Box::new(
+                crate::engine_wasmtime::WasmtimeEngine::new()?,
+            ))),
+        }
+    }
+}
+
+/// A uniform surface over an execution backend.
+pub trait ExecutionEngine {
+    /// Load a program image, rejecting malformed modules.
+    fn load_program(&mut self, program: &[u8]) -> Result<(), EngineError>;
+
+    /// Invoke the program's entry point under `budget`, returning the serialised
+    /// result, or [`EngineError::ResourceExhausted`] if the budget ran out.
+    fn invoke_entry_point(&mut self, budget: ResourceBudget) -> Result<Vec<u8>, EngineError>;
+
+    /// The engine's current `MachineState`.
+    fn state(&self) -> MachineState;
+}
@@ -0,0 +1,149 @@
+//! Wasmtime-backed execution engine
+//!
+//! An [`ExecutionEngine`] implementation backed by wasmtime, using wasmtime's
+//! built-in fuel metering to enforce the fuel half of the [`ResourceBudget`] and a
+//! [`wasmtime::StoreLimits`] resource limiter to enforce the memory half
+//! *throughout* execution: a `memory.grow` past the cap is refused (the program
+//! sees the grow fail), rather than the cap being checked once at instantiation
+//! and then ignored.  Running out of fuel surfaces as
+//! [`EngineError::ResourceExhausted`]; a refused `memory.grow` does not trap and
+//! is not reported as an error.
+//!
+//! The entry point convention mirrors the interpreter's result buffer without a
+//! host call: a module that produces output exports `invoke_main` with an `i32`
+//! return giving the byte length of a result serialised at offset `0` of the
+//! module's exported `memory`.  Modules that drive their output through host calls
+//! instead expose an `invoke_main` returning nothing and are still serviced by the
+//! interpreter path, which owns that host-call surface.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+use crate::engine::{EngineError, ExecutionEngine, ResourceBudget};
+use crate::hcall::MachineState;
+
+/// Entry point a Veracruz program exposes for its computation.
+const ENTRY_POINT_NAME: &str = "invoke_main";
+
+/// Name of the linear memory a result-producing module exports.
+const MEMORY_EXPORT_NAME: &str = "memory";
+
+/// A wasmtime-backed engine.  Fuel consumption is enabled on the `Config` so that
+/// the budget can be charged deterministically.
+pub struct WasmtimeEngine {
+    engine: wasmtime::Engine,
+    module: Option<wasmtime::Module>,
+    state: MachineState,
+}
+
+impl WasmtimeEngine {
+    /// Build a fuel-metering wasmtime engine.  `Engine::new` is fallible (an
+    /// invalid `Config` is rejected), so construction returns a `Result` rather
+    /// than papering over the error.
+    pub fn new() -> Result<Self, EngineError> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = wasmtime::Engine::new(&config)
+            .map_err(|err| EngineError::InvalidProgram(format!("{:?}", err)))?;
+        Ok(WasmtimeEngine {
+            engine,
+            module: None,
+            state: MachineState::Initial,
+        })
+    }
+}
+
+/// Map a wasmtime trap to an [`EngineError`], distinguishing budget exhaustion
+/// (running out of fuel) from a genuine program trap such as an out-of-bounds
+/// memory access.
+fn classify_trap(trap: wasmtime::Trap) -> EngineError {
+    let message = trap.to_string();
+    if message.contains("fuel") || message.contains("limit") {
+        EngineError::ResourceExhausted
+    } else {
+        EngineError::ExecutionTrap(message)
+    }
+}
+
+impl ExecutionEngine for WasmtimeEngine {
+    fn load_program(&mut self, program: &[u8]) -> Result<(), EngineError> {
+        let module = wasmtime::Module::new(&self.engine, program)
+            .map_err(|err| EngineError::InvalidProgram(format!("{:?}", err)))?;
+        self.module = Some(module);
+        self.state = MachineState::ReadyToExecute;
+        Ok(())
+    }
+
+    fn invoke_entry_point(&mut self, budget: ResourceBudget) -> Result<Vec<u8>, EngineError> {
+        let module = self
+            .module
+            .clone()
+            .ok_or_else(|| EngineError::InvalidProgram("no program loaded".to_string()))?;
+
+        // The store data is the memory limiter, so the cap is enforced across the
+        // whole run via `memory.grow` rather than sampled once at instantiation.
+        let limits = match budget.memory_cap {
+            Some(cap) => wasmtime::StoreLimitsBuilder::new().memory_size(cap).build(),
+            None => wasmtime::StoreLimitsBuilder::new().build(),
+        };
+        let mut store = wasmtime::Store::new(&self.engine, limits);
+        store.limiter(|limits| limits as &mut dyn wasmtime::ResourceLimiter);
+
+        // Charge the configured fuel; wasmtime traps with `OutOfFuel` once it is
+        // spent, which we translate into `ResourceExhausted`.
+        store
+            .add_fuel(budget.fuel)
+            .map_err(|err| EngineError::ExecutionTrap(format!("{:?}", err)))?;
+
+        let instance = wasmtime::Instance::new(&mut store, &module, &[])
+            .map_err(|err| EngineError::ExecutionTrap(format!("{:?}", err)))?;
+
+        let entry = instance
+            .get_func(&mut store, ENTRY_POINT_NAME)
+            .ok_or_else(|| EngineError::InvalidProgram("missing entry point".to_string()))?;
+
+        // A result-producing module returns the length of its output; a host-call
+        // module returns nothing.  Decide which by inspecting the function type.
+        let mut results = entry.ty(&store).results();
+        let produces_result =
+            results.len() == 1 && matches!(results.next(), Some(wasmtime::ValType::I32));
+
+        if produces_result {
+            let mut ret = [wasmtime::Val::I32(0)];
+            entry
+                .call(&mut store, &[], &mut ret)
+                .map_err(classify_trap)?;
+            self.state = MachineState::FinishedExecuting;
+            let len = match ret[0] {
+                wasmtime::Val::I32(n) if n >= 0 => n as usize,
+                _ => 0,
+            };
+            let memory = instance
+                .get_memory(&mut store, MEMORY_EXPORT_NAME)
+                .ok_or_else(|| {
+                    EngineError::ExecutionTrap(
+                        "entry point returned a length but module exports no memory".to_string(),
+                    )
+                })?;
+            let data = memory.data(&store);
+            let end = len.min(data.len());
+            Ok(data[..end].to_vec())
+        } else {
+            entry.call(&mut store, &[], &mut []).map_err(classify_trap)?;
+            self.state = MachineState::FinishedExecuting;
+            // No result channel on this module: its output is produced through the
+            // interpreter's host-call buffer, not this engine.
+            Ok(Vec::new())
+        }
+    }
+
+    fn state(&self) -> MachineState {
+        self.state
+    }
+}
@@ -0,0 +1,168 @@
+//! Append-only Merkle commitment over provisioned inputs
+//!
+//! The enclave maintains one of these trees over every provisioned package so
+//! that a result reader can be returned a signed root alongside the result, and
+//! per-package inclusion proofs on request, and thereby verify the result was
+//! computed over precisely the inputs whose hashes it knows.
+//!
+//! The tree stores only the list of leaf hashes `SHA256(package_id || payload)`;
+//! appending a package pushes one leaf.  The root and per-package inclusion proofs
+//! are folded up from the leaves on demand, combining pairs as
+//! `SHA256(left || right)` and carrying (duplicating) an odd trailing node at each
+//! level.  Recomputing rather than caching the internal nodes means a streaming
+//! round that appends new leaves never observes a stale commitment.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+use ring::digest::{digest, SHA256};
+
+/// One step of an inclusion proof: a sibling hash and whether that sibling sits
+/// to the left of the node being proved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofStep {
+    pub sibling: Vec<u8>,
+    /// `true` if the sibling is the left child (so the proved node is the right).
+    pub sibling_is_left: bool,
+}
+
+/// An inclusion proof for a single leaf: the sibling chain from leaf to root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub steps: Vec<ProofStep>,
+}
+
+/// An incremental, append-only Merkle tree over provisioned packages.
+pub struct MerkleTree {
+    /// The leaf hashes, in provisioning order; internal nodes are folded on demand.
+    leaves: Vec<Vec<u8>>,
+}
+
+impl MerkleTree {
+    pub fn new() -> Self {
+        MerkleTree { leaves: Vec::new() }
+    }
+
+    /// The number of leaves (provisioned packages) committed so far.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hash a package into a leaf: `SHA256(package_id || payload)`.
+    pub fn leaf_hash(package_id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut buffer = package_id.to_le_bytes().to_vec();
+        buffer.extend_from_slice(payload);
+        digest(&SHA256, &buffer).as_ref().to_vec()
+    }
+
+    /// Append a package, committing its leaf to the tree.
+    pub fn append(&mut self, package_id: u32, payload: &[u8]) {
+        self.leaves.push(Self::leaf_hash(package_id, payload));
+    }
+
+    /// The current Merkle root, recomputed from the leaves each call (carrying —
+    /// duplicating — odd trailing nodes) so a root observed after a streaming
+    /// round reflects the newly appended leaves.
+    pub fn root(&self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut level = self.leaves.clone();
+        while level.len() > 1 {
+            let mut next = Vec::with_capacity((level.len() + 1) / 2);
+            let mut i = 0;
+            while i < level.len() {
+                let left = &level[i];
+                // Carry (duplicate) an odd trailing node.
+                let right = if i + 1 < level.len() {
+                    &level[i + 1]
+                } else {
+                    &level[i]
+                };
+                next.push(Self::combine(left, right));
+                i += 2;
+            }
+            level = next;
+        }
+        Some(level.into_iter().next().unwrap())
+    }
+
+    /// Build an inclusion proof for leaf `index`, the sibling hashes from the
+    /// leaf up to the root together with a left/right bit per step.
+    pub fn inclusion_proof(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut steps = Vec::new();
+        let mut level = self.leaves.clone();
+        let mut idx = index;
+        while level.len() > 1 {
+            let sibling_index = if idx % 2 == 0 {
+                // Right sibling; carry self when it is the odd trailing node.
+                if idx + 1 < level.len() {
+                    idx + 1
+                } else {
+                    idx
+                }
+            } else {
+                idx - 1
+            };
+            steps.push(ProofStep {
+                sibling: level[sibling_index].clone(),
+                sibling_is_left: sibling_index < idx,
+            });
+            // Fold this level up, carrying odd trailing nodes.
+            let mut next = Vec::with_capacity((level.len() + 1) / 2);
+            let mut i = 0;
+            while i < level.len() {
+                let left = &level[i];
+                let right = if i + 1 < level.len() {
+                    &level[i + 1]
+                } else {
+                    &level[i]
+                };
+                next.push(Self::combine(left, right));
+                i += 2;
+            }
+            level = next;
+            idx /= 2;
+        }
+        Some(InclusionProof { steps })
+    }
+
+    fn combine(left: &[u8], right: &[u8]) -> Vec<u8> {
+        let mut buffer = left.to_vec();
+        buffer.extend_from_slice(right);
+        digest(&SHA256, &buffer).as_ref().to_vec()
+    }
+}
+
+impl Default for MerkleTree {
+    fn default() -> Self {
+        MerkleTree::new()
+    }
+}
+
+/// Verify an inclusion proof: fold `leaf` up through the proof steps and check
+/// the result equals `root`.
+pub fn verify_inclusion_proof(leaf: &[u8], proof: &InclusionProof, root: &[u8]) -> bool {
+    let mut current = leaf.to_vec();
+    for step in &proof.steps {
+        current = if step.sibling_is_left {
+            MerkleTree::combine(&step.sibling, &current)
+        } else {
+            MerkleTree::combine(&current, &step.sibling)
+        };
+    }
+    current == root
+}
@@ -0,0 +1,54 @@
+//! Merkle inclusion-proof request/response messages
+//!
+//! The wire pair a result reader uses to request, and parse, a per-package
+//! inclusion proof against the enclave's append-only commitment.  The enclave
+//! signs the Merkle root with its attested key, so a parsed proof chains back to
+//! the attestation already performed during the session.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+use serde::{Deserialize, Serialize};
+
+/// One sibling hash and its side, mirroring `chihuahua::merkle::ProofStep`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MerkleProofStep {
+    pub sibling: Vec<u8>,
+    pub sibling_is_left: bool,
+}
+
+/// A request for the inclusion proof of a single provisioned package.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MerkleProofRequest {
+    pub package_id: u32,
+}
+
+/// The enclave's response: the signed root and the sibling chain for the package.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MerkleProofResponse {
+    /// The current Merkle root the proof verifies against.
+    pub root: Vec<u8>,
+    /// The enclave's signature over `root`, rooted in the attestation.
+    pub root_signature: Vec<u8>,
+    /// The leaf hash of the requested package.
+    pub leaf: Vec<u8>,
+    /// The sibling chain from leaf to root.
+    pub steps: Vec<MerkleProofStep>,
+}
+
+/// Serialise a request for the inclusion proof of `package_id`.
+pub fn serialize_request_merkle_proof(package_id: u32) -> Result<Vec<u8>, crate::ColimaError> {
+    let request = MerkleProofRequest { package_id };
+    bincode::serialize(&request).map_err(|_| crate::ColimaError::SerializationError)
+}
+
+/// Parse an enclave inclusion-proof response.
+pub fn parse_merkle_proof(buffer: &[u8]) -> Result<MerkleProofResponse, crate::ColimaError> {
+    bincode::deserialize(buffer).map_err(|_| crate::ColimaError::SerializationError)
+}
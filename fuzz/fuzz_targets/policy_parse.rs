@@ -0,0 +1,39 @@
+//! Fuzz target: policy parsing feeding enclave initialisation
+//!
+//! Exercises the first untrusted entry point the integration tests hammer by
+//! hand: arbitrary bytes are fed into `VeracruzPolicy::from_json`, and any input
+//! that parses is then required to initialise an enclave.  This continuously
+//! checks the invariant `test_phase1_init_destroy_enclave` probes with a fixed
+//! handful of cases: an `Ok(policy)` must never produce an `Err` from
+//! `SinaloaEnclave::new`, and neither call may panic.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // `from_json` takes a string; non-UTF-8 inputs are simply not policies.
+    let policy_json = match std::str::from_utf8(data) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+
+    if veracruz_utils::VeracruzPolicy::from_json(policy_json).is_ok() {
+        // A policy that parses cleanly must also stand up an enclave.
+        let result = sinaloa::SinaloaEnclave::new(policy_json);
+        assert!(
+            result.is_ok(),
+            "a valid policy failed to initialise an enclave: {:?}",
+            result.err()
+        );
+    }
+});
@@ -0,0 +1,109 @@
+//! Fuzz target: structure-aware provisioning state machine
+//!
+//! Generates random *sequences* of provisioning messages and replays them
+//! against a freshly initialised enclave, checking the invariant the
+//! `test_phase4_*` cases probe by hand: the `MachineState` only ever advances
+//! monotonically through
+//!
+//! ```text
+//! INITIAL -> DATA_SOURCES_LOADING -> STREAM_SOURCE_LOADING
+//!         -> READY_TO_EXECUTE -> FINISHED_EXECUTING
+//! ```
+//!
+//! and the enclave never panics or yields a result before reaching
+//! `READY_TO_EXECUTE`.  This turns the fixed negative cases into continuous,
+//! coverage-guided coverage of provisioning-order bugs.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+/// A single provisioning action the fuzzer can emit, mirroring the colima
+/// request kinds the integration harness sends.
+#[derive(Arbitrary, Debug)]
+enum ProvisionOp {
+    /// Upload a program image.
+    Program(Vec<u8>),
+    /// Provision a static data source `(index, bytes)`.
+    Data { index: u32, bytes: Vec<u8> },
+    /// Provision a stream source `(index, bytes)`.
+    Stream { index: u32, bytes: Vec<u8> },
+    /// Request the computation result.
+    RequestResult,
+}
+
+impl sinaloa_fuzz::ProvisionAction for ProvisionOp {
+    fn serialize(&self) -> Vec<u8> {
+        match self {
+            ProvisionOp::Program(bytes) => {
+                colima::serialize_program(bytes).unwrap_or_default()
+            }
+            ProvisionOp::Data { index, bytes } => {
+                colima::serialize_program_data(bytes, *index).unwrap_or_default()
+            }
+            ProvisionOp::Stream { index, bytes } => {
+                colima::serialize_stream(bytes, *index).unwrap_or_default()
+            }
+            ProvisionOp::RequestResult => {
+                colima::serialize_request_result().unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// The measured enclave `MachineState`, encoded as the same `u8` ladder the
+/// enclave reports, so monotonicity is a simple integer comparison.
+const STATE_INITIAL: u8 = 0;
+const STATE_READY_TO_EXECUTE: u8 = 3;
+
+/// A fixed, known-valid policy so the fuzzer spends its budget on provisioning
+/// order rather than re-discovering the policy grammar (covered by
+/// `policy_parse`).
+const POLICY_JSON: &str = include_str!("../../test-collateral/get_random_policy.json");
+
+fuzz_target!(|ops: Vec<ProvisionOp>| {
+    let sinaloa = match sinaloa::SinaloaEnclave::new(POLICY_JSON) {
+        Ok(sinaloa) => sinaloa,
+        // A loaded policy that refuses to initialise is a separate bug class,
+        // caught by `policy_parse`; nothing to replay here.
+        Err(_) => return,
+    };
+
+    let mut replay = sinaloa_fuzz::ProvisioningReplay::new(&sinaloa);
+    let mut last_state = STATE_INITIAL;
+    for op in ops {
+        // A result requested before the enclave is ready must be rejected, never
+        // served and never a panic.
+        if let ProvisionOp::RequestResult = op {
+            if replay.state() < STATE_READY_TO_EXECUTE {
+                assert!(
+                    replay.request_result().is_err(),
+                    "enclave served a result before READY_TO_EXECUTE"
+                );
+                continue;
+            }
+        }
+
+        // Replaying an ill-ordered message may legitimately fail, but it must not
+        // panic and must not move the state machine backwards.
+        let _ = replay.apply(op);
+        let state = replay.state();
+        assert!(
+            state >= last_state,
+            "MachineState regressed from {} to {}",
+            last_state,
+            state
+        );
+        last_state = state;
+    }
+});
@@ -0,0 +1,89 @@
+//! Shared helpers for the Sinaloa fuzz targets
+//!
+//! Keeps the enclave-driving boilerplate out of the individual targets so that
+//! new structure-aware harnesses can reuse the same provisioning replay.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+use sinaloa::Sinaloa;
+
+/// Drives a sequence of fuzzer-generated provisioning actions against a single
+/// enclave session and exposes the enclave's measured `MachineState` so targets
+/// can assert the monotonic-advance invariant.
+pub struct ProvisioningReplay<'a> {
+    sinaloa: &'a dyn Sinaloa,
+    session_id: u32,
+}
+
+impl<'a> ProvisioningReplay<'a> {
+    /// Open a fresh session against `sinaloa` for replay.
+    pub fn new(sinaloa: &'a dyn Sinaloa) -> Self {
+        let session_id = sinaloa.new_tls_session().unwrap_or(0);
+        ProvisioningReplay {
+            sinaloa,
+            session_id,
+        }
+    }
+
+    /// The enclave's current `MachineState` as a `u8`, or `0` (INITIAL) if it
+    /// could not be read.
+    pub fn state(&self) -> u8 {
+        self.request_state().unwrap_or(0)
+    }
+
+    /// Apply one provisioning action, returning the enclave's response bytes.
+    pub fn apply<Op: ProvisionAction>(&mut self, op: Op) -> Result<Vec<u8>, ()> {
+        self.round_trip(&op.serialize())
+    }
+
+    /// Request the computation result, returning `Err` unless the enclave answers
+    /// with a success status; used to assert pre-READY requests fail.
+    pub fn request_result(&mut self) -> Result<Vec<u8>, ()> {
+        let request = colima::serialize_request_result().map_err(|_| ())?;
+        let response = self.round_trip(&request)?;
+        // A refusal arrives as a response frame carrying a failure status.
+        let parsed = colima::parse_mexico_city_response(&response).map_err(|_| ())?;
+        if parsed.get_status() == colima::ResponseStatus::SUCCESS {
+            Ok(response)
+        } else {
+            Err(())
+        }
+    }
+
+    fn request_state(&self) -> Option<u8> {
+        let request = colima::serialize_request_enclave_state().ok()?;
+        let response = self.round_trip_ref(&request).ok()?;
+        let parsed = colima::parse_mexico_city_response(&response).ok()?;
+        if parsed.has_state() {
+            parsed.get_state().get_state().first().copied()
+        } else {
+            None
+        }
+    }
+
+    fn round_trip(&mut self, request: &[u8]) -> Result<Vec<u8>, ()> {
+        self.round_trip_ref(request)
+    }
+
+    fn round_trip_ref(&self, request: &[u8]) -> Result<Vec<u8>, ()> {
+        let (_alive, output) = self
+            .sinaloa
+            .tls_data(self.session_id, request.to_vec())
+            .map_err(|_| ())?;
+        output
+            .and_then(|frames| frames.into_iter().next())
+            .ok_or(())
+    }
+}
+
+/// A provisioning action that can be turned into a colima request frame.
+pub trait ProvisionAction {
+    fn serialize(&self) -> Vec<u8>;
+}
@@ -0,0 +1,134 @@
+//! Phase-timing benchmark subsystem for the Sinaloa performance tests.
+//!
+//! The performance tests drive `test_template` once per data file and used to
+//! sprinkle `Instant::now()`/`elapsed()` values straight into `info!` lines.
+//! Across `iterate_over_data` that produced a wall of unaggregated numbers that
+//! was useless for tracking regressions between commits.
+//!
+//! A [`BenchmarkCollector`] instead accumulates the samples of each named phase
+//! -- setup, enclave init, program/data provisioning, every streaming round,
+//! result retrieval, shutdown -- across all the data files in a run.  At the end
+//! of the run it computes a percentile histogram (min/p50/p90/p99/max plus the
+//! sample count) per phase and serialises the summary to a machine-readable JSON
+//! file so CI can diff throughput between commits.
+//!
+//! Insertion is O(1) amortised (a push onto a per-phase vector); the percentiles
+//! are computed once, at the end, by sorting each vector.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+
+/// The percentile summary of a single phase's samples, all in microseconds.
+#[derive(Clone, Debug)]
+pub struct PhaseSummary {
+    /// Number of samples recorded for the phase.
+    pub count: usize,
+    /// Fastest sample.
+    pub min: u128,
+    /// Median (50th percentile).
+    pub p50: u128,
+    /// 90th percentile.
+    pub p90: u128,
+    /// 99th percentile.
+    pub p99: u128,
+    /// Slowest sample.
+    pub max: u128,
+}
+
+/// Accumulates per-phase timing samples across every data file in a run.
+///
+/// The map is keyed by phase name and preserves insertion-independent ordering
+/// (alphabetical) so successive runs emit the summary in a stable order, which
+/// keeps the CI diff small.
+pub struct BenchmarkCollector {
+    phases: BTreeMap<String, Vec<u128>>,
+}
+
+impl BenchmarkCollector {
+    /// An empty collector.
+    pub fn new() -> Self {
+        BenchmarkCollector {
+            phases: BTreeMap::new(),
+        }
+    }
+
+    /// Record one `elapsed_micros` sample against `phase`.  Amortised O(1).
+    pub fn record(&mut self, phase: &str, elapsed_micros: u128) {
+        self.phases
+            .entry(phase.to_string())
+            .or_insert_with(Vec::new)
+            .push(elapsed_micros);
+    }
+
+    /// Discard every recorded sample, readying the collector for the next run.
+    pub fn clear(&mut self) {
+        self.phases.clear();
+    }
+
+    /// Collapse the recorded samples into a percentile summary per phase.
+    pub fn summary(&self) -> BTreeMap<String, PhaseSummary> {
+        self.phases
+            .iter()
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|(phase, samples)| {
+                let mut sorted = samples.clone();
+                sorted.sort_unstable();
+                let summary = PhaseSummary {
+                    count: sorted.len(),
+                    min: sorted[0],
+                    p50: percentile(&sorted, 50.0),
+                    p90: percentile(&sorted, 90.0),
+                    p99: percentile(&sorted, 99.0),
+                    max: sorted[sorted.len() - 1],
+                };
+                (phase.clone(), summary)
+            })
+            .collect()
+    }
+
+    /// Serialise the summary to `path` as JSON, one object per phase, so CI can
+    /// diff throughput between commits without re-parsing the log.
+    pub fn write_summary(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let summary = self.summary();
+        let mut file = File::create(path)?;
+        writeln!(file, "{{")?;
+        let last = summary.len().saturating_sub(1);
+        for (index, (phase, stats)) in summary.iter().enumerate() {
+            let comma = if index == last { "" } else { "," };
+            writeln!(
+                file,
+                "  {:?}: {{ \"count\": {}, \"min\": {}, \"p50\": {}, \"p90\": {}, \"p99\": {}, \"max\": {} }}{}",
+                phase, stats.count, stats.min, stats.p50, stats.p90, stats.p99, stats.max, comma
+            )?;
+        }
+        writeln!(file, "}}")?;
+        Ok(())
+    }
+}
+
+impl Default for BenchmarkCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Percentile over an already-sorted, non-empty slice: the element at index
+/// `p / 100 * (len - 1)`, rounded to the nearest index.
+fn percentile(sorted: &[u128], p: f64) -> u128 {
+    let last = sorted.len() - 1;
+    let rank = (p / 100.0 * last as f64).round() as usize;
+    sorted[rank.min(last)]
+}
@@ -12,7 +12,10 @@
 //! See the `LICENSE.markdown` file in the Veracruz root directory for
 //! information on licensing and copyright.
 
+mod benchmark;
+
 mod tests {
+    use crate::benchmark::BenchmarkCollector;
     use actix_rt::System;
     use base64;
     use colima;
@@ -24,6 +27,8 @@ mod tests {
     use rand::Rng;
     use ring;
     use serde::Deserialize;
+    use chihuahua::engine::ExecutionStrategy;
+    use sinaloa::reputation::{PeerState, ReputationConfig, ReputationTable};
     use sinaloa::sinaloa::*;
     #[cfg(feature = "sgx")]
     use sinaloa::SinaloaSGX as SinaloaEnclave;
@@ -36,16 +41,39 @@ mod tests {
         path::Path,
         sync::{
             atomic::{AtomicBool, Ordering},
-            Mutex, Once,
+            Arc, Mutex, Once,
         },
         thread,
-        time::Instant,
+        time::{Duration, Instant},
         vec::Vec,
     };
     #[cfg(feature = "sgx")]
     use stringreader;
     use tabasco;
 
+    /// How the attestation challenge handed to `attestation_flow` is produced.
+    #[derive(Clone, Copy, Debug)]
+    enum ChallengeMode {
+        /// A fresh 32-byte random nonce -- the historical behaviour.
+        Random,
+        /// 32 bytes of RFC 5705 exported keying material from the provisioning
+        /// TLS session, binding the attestation to that channel.
+        ChannelBound,
+    }
+
+    /// Which private-key encoding, and hence which client-auth signature scheme,
+    /// to prefer when a key file could be read as more than one.
+    #[derive(Clone, Copy, Debug)]
+    enum ClientKeyType {
+        /// Try PKCS#8 first (covering ECDSA and Ed25519 as well as RSA), then
+        /// fall back to a PKCS#1 RSA section.
+        Auto,
+        /// A PKCS#1 RSA private key.
+        Rsa,
+        /// A PKCS#8 private key (RSA, ECDSA, or Ed25519).
+        Pkcs8,
+    }
+
     // Constants corresponding to the `chihuahua::hcall::MachineState` enum which is
     // encoded as a `u8` value when servicing an enclave state request.  Included here
     // to avoid adding chihuahua as a direct dependency of this crate.
@@ -75,6 +103,10 @@ mod tests {
         "../test-collateral/number-stream-accumulation.json";
     const CLIENT_CERT: &'static str = "../test-collateral/client_rsa_cert.pem";
     const CLIENT_KEY: &'static str = "../test-collateral/client_rsa_key.pem";
+    // An ECDSA client identity whose key is stored in PKCS#8, to exercise the
+    // non-RSA branch of `read_priv_key_file`.
+    const CLIENT_EC_CERT: &'static str = "../test-collateral/client_ec_cert.pem";
+    const CLIENT_EC_KEY: &'static str = "../test-collateral/client_ec_key.pem";
     const UNAUTHORIZED_CERT: &'static str = "../test-collateral/data_client_cert.pem";
     const UNAUTHORIZED_KEY: &'static str = "../test-collateral/data_client_key.pem";
     // Programs
@@ -119,6 +151,156 @@ mod tests {
         // thus stops another thread. Without this hack, a failure can cause non-termination.
         static ref CONTINUE_FLAG_HASH: Mutex<HashMap<u32,bool>> = Mutex::new(HashMap::<u32,bool>::new());
         static ref NEXT_TICKET: Mutex<u32> = Mutex::new(0);
+        // Server-side client reputation table, shared across sessions so offences
+        // by a peer in one connection follow it into the next.
+        static ref REPUTATION: Mutex<ReputationTable> =
+            Mutex::new(ReputationTable::new(ReputationConfig::default()));
+        // Phase-timing samples accumulated by `test_template`.  Like the flags
+        // above this is a global, shared by every `test_template` call in a run;
+        // the performance tests clear it before a run and report it afterwards.
+        static ref BENCH: Mutex<BenchmarkCollector> = Mutex::new(BenchmarkCollector::new());
+        // Cached attestation results, keyed by enclave name and pinned cert hash,
+        // so a client reconnecting to an enclave it has already attested can skip
+        // the expensive proxy-PSA token exchange until the ticket expires.
+        static ref ATTESTATION_CACHE: Mutex<HashMap<(String, Vec<u8>), AttestationTicket>> =
+            Mutex::new(HashMap::new());
+        // Maximum TLS fragment size negotiated for each session, keyed by ticket.
+        // `None` leaves rustls free to emit full-sized records; `Some(n)` caps both
+        // the record size (via `set_mtu`) and the plaintext `client_tls_send` feeds
+        // per `write_tls`, so multi-megabyte provisioning never buffers the whole
+        // payload at once.
+        static ref MAX_FRAGMENT: Mutex<HashMap<u32, Option<usize>>> = Mutex::new(HashMap::new());
+        // Whether teardown of each session must confirm a graceful `close_notify`
+        // exchange, keyed by ticket.  When set, the client sends its own
+        // `close_notify` and requires the enclave's in return, so a truncation of
+        // the final response is reported as `UncleanShutdown` rather than passing
+        // for a normal close.
+        static ref CHECK_CLOSE_NOTIFY: Mutex<HashMap<u32, bool>> = Mutex::new(HashMap::new());
+    }
+
+    lazy_static! {
+        // Number of proxy-PSA attestation exchanges actually performed, keyed by
+        // the same (enclave name, pinned cert hash) identity as `ATTESTATION_CACHE`.
+        // The resumption tests assert that a resumed session re-used a cached ticket
+        // instead of re-attesting; keying per enclave identity keeps that assertion
+        // isolated from other `test_template` cases cargo runs concurrently, which a
+        // single global counter would race against.
+        static ref ATTESTATION_EXCHANGES: Mutex<HashMap<(String, Vec<u8>), usize>> =
+            Mutex::new(HashMap::new());
+    }
+
+    /// Number of attestation exchanges recorded for one enclave identity, used by
+    /// the resumption tests to check a reconnect resumed from cache.
+    fn attestation_exchange_count(enclave_name: &str, self_signed_hash: &[u8]) -> usize {
+        ATTESTATION_EXCHANGES
+            .lock()
+            .map(|map| {
+                map.get(&(enclave_name.to_string(), self_signed_hash.to_vec()))
+                    .copied()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0)
+    }
+
+    // How long a cached attestation ticket is trusted before a reconnect must
+    // re-attest, mirroring the finite lifetime of a TLS session ticket.
+    const ATTESTATION_TICKET_LIFETIME: Duration = Duration::from_secs(600);
+
+    /// A cached attestation result: the enclave cert hash the token bound, and the
+    /// instant past which the ticket must not be resumed.
+    #[derive(Clone)]
+    struct AttestationTicket {
+        enclave_cert_hash: Vec<u8>,
+        expiry: Instant,
+    }
+
+    /// Attest the enclave, or reuse a cached ticket when the pinned `self_signed_hash`
+    /// still matches a stored, non-expired entry for `enclave_name`.
+    ///
+    /// On a cache miss or an expired/changed ticket this runs the full
+    /// [`attestation_flow`] and stores the result; on a hit it returns the cached
+    /// enclave cert hash without a second token exchange.
+    fn attest_or_resume(
+        enclave_name: &str,
+        self_signed_hash: &[u8],
+        tabasco_url: &String,
+        expected_enclave_hash: &String,
+        sinaloa: &dyn sinaloa::Sinaloa,
+        challenge: &[u8; 32],
+    ) -> Result<Vec<u8>, SinaloaError> {
+        let key = (enclave_name.to_string(), self_signed_hash.to_vec());
+        if let Some(ticket) = ATTESTATION_CACHE.lock()?.get(&key) {
+            if ticket.expiry > Instant::now() {
+                info!(
+                    "attestation: resuming cached ticket for enclave {}",
+                    enclave_name
+                );
+                return Ok(ticket.enclave_cert_hash.clone());
+            }
+        }
+        let enclave_cert_hash =
+            attestation_flow(tabasco_url, expected_enclave_hash, sinaloa, challenge)?;
+        *ATTESTATION_EXCHANGES.lock()?.entry(key.clone()).or_insert(0) += 1;
+        ATTESTATION_CACHE.lock()?.insert(
+            key,
+            AttestationTicket {
+                enclave_cert_hash: enclave_cert_hash.clone(),
+                expiry: Instant::now() + ATTESTATION_TICKET_LIFETIME,
+            },
+        );
+        Ok(enclave_cert_hash)
+    }
+
+    /// Record the elapsed time of a phase, started at `began`, into the shared
+    /// [`BenchmarkCollector`].  Replaces the ad-hoc `info!` timing prints so the
+    /// samples can be aggregated into a histogram at the end of a run.
+    fn record_phase(phase: &str, began: Instant) {
+        BENCH
+            .lock()
+            .unwrap()
+            .record(phase, began.elapsed().as_micros());
+    }
+
+    /// Emit the accumulated phase histograms for a performance run and write a
+    /// machine-readable summary CI can diff, then reset the collector so the next
+    /// run starts clean.  `label` names both the log lines and the output file.
+    fn report_benchmark(label: &str) {
+        let mut bench = BENCH.lock().unwrap();
+        for (phase, stats) in bench.summary() {
+            info!(
+                "benchmark[{}] {:>18}: count={:>4} min={:>9} p50={:>9} p90={:>9} p99={:>9} max={:>9} (μs)",
+                label, phase, stats.count, stats.min, stats.p50, stats.p90, stats.p99, stats.max
+            );
+        }
+        let path = format!("../test-benchmark-{}.json", label);
+        if let Err(e) = bench.write_summary(&path) {
+            info!("benchmark[{}]: could not write summary to {}: {:?}", label, path, e);
+        }
+        bench.clear();
+    }
+
+    /// The reputation fingerprint of a client, the SHA-256 of its certificate.
+    fn client_fingerprint(client_cert: &rustls::Certificate) -> String {
+        hex::encode(ring::digest::digest(&ring::digest::SHA256, client_cert.as_ref()).as_ref())
+    }
+
+    #[test]
+    /// Repeated authentication/TLS failures must drive a peer past the ban
+    /// threshold, after which its handshake is refused.
+    fn test_phase2_reputation_bans_after_repeated_failures() {
+        let mut table = ReputationTable::new(ReputationConfig::default());
+        let peer = "deadbeef";
+        assert!(table.is_allowed(peer), "a fresh peer must be allowed");
+        // Defaults: -2.0 per failure, ban at -5.0, so three failures ban the peer.
+        table.record_failure(peer);
+        assert!(table.is_allowed(peer), "one failure must not ban");
+        table.record_failure(peer);
+        table.record_failure(peer);
+        assert!(
+            !table.is_allowed(peer),
+            "a peer past the ban threshold must be refused"
+        );
+        assert_eq!(table.current_state(peer), PeerState::Banned);
     }
 
     pub fn setup(tabasco_url: String) -> u32 {
@@ -235,11 +417,117 @@ mod tests {
 
         let sinaloa = ret.unwrap();
 
-        let enclave_cert_hash_ret =
-            attestation_flow(&policy.tabasco_url(), &policy.mexico_city_hash(), &sinaloa);
+        let challenge = rand::thread_rng().gen::<[u8; 32]>();
+        let enclave_cert_hash_ret = attestation_flow(
+            &policy.tabasco_url(),
+            &policy.mexico_city_hash(),
+            &sinaloa,
+            &challenge,
+        );
         assert!(enclave_cert_hash_ret.is_ok())
     }
 
+    /// Auxiliary function: build a signed result envelope with a fresh P-256 key,
+    /// binding `program_hash` and `result`, for the envelope verification tests.
+    fn signed_envelope(
+        program_hash: Vec<u8>,
+        result: Vec<u8>,
+    ) -> sinaloa::result_envelope::ResultEnvelope {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = ring::signature::EcdsaKeyPair::generate_pkcs8(
+            &ring::signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+            &rng,
+        )
+        .unwrap();
+        let key_pair = ring::signature::EcdsaKeyPair::from_pkcs8(
+            &ring::signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+            pkcs8.as_ref(),
+        )
+        .unwrap();
+        let public_key = key_pair.public_key().as_ref().to_vec();
+        sinaloa::result_envelope::ResultEnvelope::new_signed(
+            &key_pair,
+            public_key,
+            program_hash,
+            vec![],
+            vec![],
+            result,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    /// A well-formed, signed envelope whose program hash matches the policy
+    /// verifies, and the bound result survives round-tripping.
+    fn test_phase2_result_envelope_verifies() {
+        let (policy, _, _) = read_policy(GET_RANDOM_POLICY).unwrap();
+        let program_hash = hex::decode(policy.pi_hash()).unwrap();
+        let result = pinecone::to_vec(&vec![1u8, 2, 3]).unwrap();
+        let envelope = signed_envelope(program_hash, result);
+        // In a live flow the attested key hash comes from attestation; here we
+        // recompute it from the signing public key (the domain the attestation
+        // result reports) rather than copying the field under test.
+        let attested = ring::digest::digest(&ring::digest::SHA256, &envelope.public_key)
+            .as_ref()
+            .to_vec();
+        let verified =
+            sinaloa::result_envelope::verify_result_envelope(&policy, &attested, &envelope);
+        assert!(verified.is_ok(), "error:{:?}", verified.err());
+    }
+
+    #[test]
+    /// An envelope signed by a key that is not the attested enclave identity must
+    /// be rejected: the attestation-pinned hash will not match the signing key.
+    fn test_phase2_result_envelope_unattested_key() {
+        let (policy, _, _) = read_policy(GET_RANDOM_POLICY).unwrap();
+        let program_hash = hex::decode(policy.pi_hash()).unwrap();
+        let result = pinecone::to_vec(&vec![1u8, 2, 3]).unwrap();
+        let envelope = signed_envelope(program_hash, result);
+        // A hash that is not the one the attestation pinned for the enclave.
+        let wrong_attested = vec![0xbbu8; 32];
+        assert!(
+            sinaloa::result_envelope::verify_result_envelope(&policy, &wrong_attested, &envelope)
+                .is_err(),
+            "an envelope not bound to the attested enclave should fail verification"
+        );
+    }
+
+    #[test]
+    /// Flipping a single result byte invalidates the signature, so verification
+    /// must fail.
+    fn test_phase2_result_envelope_tampered_result() {
+        let (policy, _, _) = read_policy(GET_RANDOM_POLICY).unwrap();
+        let program_hash = hex::decode(policy.pi_hash()).unwrap();
+        let result = pinecone::to_vec(&vec![1u8, 2, 3]).unwrap();
+        let mut envelope = signed_envelope(program_hash, result);
+        let attested = ring::digest::digest(&ring::digest::SHA256, &envelope.public_key)
+            .as_ref()
+            .to_vec();
+        envelope.result[0] ^= 0xff;
+        assert!(
+            sinaloa::result_envelope::verify_result_envelope(&policy, &attested, &envelope)
+                .is_err(),
+            "tampered result should fail verification"
+        );
+    }
+
+    #[test]
+    /// An envelope bound to a program hash other than the policy's must be
+    /// rejected even though its signature is internally consistent.
+    fn test_phase2_result_envelope_program_hash_mismatch() {
+        let (policy, _, _) = read_policy(GET_RANDOM_POLICY).unwrap();
+        let result = pinecone::to_vec(&vec![1u8, 2, 3]).unwrap();
+        let envelope = signed_envelope(vec![0xaau8; 32], result);
+        let attested = ring::digest::digest(&ring::digest::SHA256, &envelope.public_key)
+            .as_ref()
+            .to_vec();
+        assert!(
+            sinaloa::result_envelope::verify_result_envelope(&policy, &attested, &envelope)
+                .is_err(),
+            "mismatched program hash should fail verification"
+        );
+    }
+
     #[test]
     #[ignore]
     /// Test if the detect for calling `debug!` in enclave works.
@@ -273,11 +561,16 @@ mod tests {
         let client_key_filename = "../test-collateral/client_rsa_key.pem";
         let cert_hash = ring::digest::digest(&ring::digest::SHA256, enclave_cert.as_ref());
 
+        let session_store: Arc<dyn rustls::StoresClientSessions> =
+            Arc::new(rustls::ClientSessionMemoryCache::new(32));
         let mut _client_session = create_client_test_session(
             &sinaloa,
             client_cert_filename,
             client_key_filename,
             cert_hash.as_ref().to_vec(),
+            session_store,
+            ClientKeyType::Auto,
+            None,
         );
     }
 
@@ -295,10 +588,86 @@ mod tests {
             &[],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
+        );
+        assert!(result.is_ok(), "error:{:?}", result);
+    }
+
+    #[test]
+    /// Integration test:
+    /// policy: PiProvider, DataProvider and ResultReader is the same party
+    /// computation: random-source, returning a vec of random u8
+    /// data sources: none
+    /// client identity: an ECDSA key stored in PKCS#8, exercising the non-RSA
+    /// branch of `read_priv_key_file` alongside the default RSA client.
+    fn test_phase2_random_source_no_data_ec_client_key() {
+        let result = test_template::<Vec<u8>>(
+            GET_RANDOM_POLICY,
+            CLIENT_EC_CERT,
+            CLIENT_EC_KEY,
+            Some(RANDOM_SOURCE_WASM),
+            &[],
+            &[],
+            false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Pkcs8,
+            None,
+            false,
         );
         assert!(result.is_ok(), "error:{:?}", result);
     }
 
+    #[cfg(feature = "quic")]
+    #[test]
+    /// Integration test for the QUIC transport backend:
+    /// policy: PiProvider, DataProvider and ResultReader is the same party
+    /// transport: a real QUIC connection, each colima exchange on its own stream
+    /// Drives the policy-hash check over [`QuicTransport`] rather than the mpsc
+    /// pipe.  Requires a QUIC-enabled enclave listening at `VERACRUZ_QUIC_ADDR`
+    /// (default `127.0.0.1:4433`); built only under the `quic` feature.
+    fn test_phase2_policy_hash_over_quic() {
+        let policy_json = std::fs::read_to_string(GET_RANDOM_POLICY).unwrap();
+        let sinaloa = SinaloaEnclave::new(&policy_json).unwrap();
+
+        let self_signed_hash = {
+            let enclave_cert = enclave_self_signed_cert(&sinaloa).unwrap();
+            ring::digest::digest(&ring::digest::SHA256, enclave_cert.as_ref())
+                .as_ref()
+                .to_vec()
+        };
+        let policy_hash = {
+            let digest = ring::digest::digest(&ring::digest::SHA256, policy_json.as_bytes());
+            hex::encode(&digest.as_ref().to_vec())
+        };
+        let server_addr = std::env::var("VERACRUZ_QUIC_ADDR")
+            .unwrap_or_else(|_| "127.0.0.1:4433".to_string())
+            .parse()
+            .expect("VERACRUZ_QUIC_ADDR is not a socket address");
+
+        let mut transport = QuicTransport::connect(
+            &sinaloa,
+            CLIENT_CERT,
+            CLIENT_KEY,
+            self_signed_hash,
+            ClientKeyType::Auto,
+            server_addr,
+        )
+        .unwrap();
+
+        let result = check_policy_hash(&policy_hash, &mut transport);
+        assert!(result.is_ok(), "error:{:?}", result);
+        transport.close_notify().unwrap();
+    }
+
     #[test]
     /// Attempt to fetch the result without program nor data
     fn test_phase2_random_source_no_program_no_data() {
@@ -310,6 +679,13 @@ mod tests {
             &[],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -325,6 +701,13 @@ mod tests {
             &[],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -340,6 +723,13 @@ mod tests {
             &[],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -355,6 +745,13 @@ mod tests {
             &[],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -370,6 +767,13 @@ mod tests {
             &[],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -385,6 +789,13 @@ mod tests {
             &[(0, LINEAR_REGRESSION_DATA)],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -413,6 +824,61 @@ mod tests {
             &[(0, LINEAR_REGRESSION_DATA)],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
+        );
+        assert!(result.is_ok(), "error:{:?}", result);
+    }
+
+    #[test]
+    /// As `test_phase2_linear_regression_single_data_no_attestation`, but forcing
+    /// a tiny TLS fragment so the program and data are chunked across many records
+    /// and the enclave must reassemble the fragmented colima messages.
+    fn test_phase2_linear_regression_single_data_small_fragment() {
+        let result = test_template::<LinearRegression>(
+            LINEAR_REGRESSION_POLICY,
+            CLIENT_CERT,
+            CLIENT_KEY,
+            Some(LINEAR_REGRESSION_WASM),
+            &[(0, LINEAR_REGRESSION_DATA)],
+            &[],
+            false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            Some(1024),
+            false,
+        );
+        assert!(result.is_ok(), "error:{:?}", result);
+    }
+
+    #[test]
+    /// As `test_phase2_linear_regression_single_data_no_attestation`, but requiring
+    /// a graceful `close_notify` at teardown: a clean run must still succeed with
+    /// the shutdown check armed.
+    fn test_phase2_linear_regression_single_data_close_notify() {
+        let result = test_template::<LinearRegression>(
+            LINEAR_REGRESSION_POLICY,
+            CLIENT_CERT,
+            CLIENT_KEY,
+            Some(LINEAR_REGRESSION_WASM),
+            &[(0, LINEAR_REGRESSION_DATA)],
+            &[],
+            false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            true,
         );
         assert!(result.is_ok(), "error:{:?}", result);
     }
@@ -428,6 +894,13 @@ mod tests {
             &[],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -458,6 +931,13 @@ mod tests {
             ],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_ok(), "error:{:?}", result);
     }
@@ -476,6 +956,13 @@ mod tests {
             &[(0, STRING_1_DATA), (1, STRING_2_DATA)],
             &[],
             false,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_ok(), "error:{:?}", result);
     }
@@ -497,6 +984,40 @@ mod tests {
             &[(0, LINEAR_REGRESSION_DATA)],
             &[],
             true,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            // reconnect twice to exercise the attestation-ticket cache
+            2,
+            ClientKeyType::Auto,
+            None,
+            false,
+        );
+        assert!(result.is_ok(), "error:{:?}", result);
+    }
+
+    #[test]
+    /// Integration test:
+    /// same computation as `test_phase3_linear_regression_one_data_with_attestation`,
+    /// but the attestation challenge is bound to the TLS channel's exported keying
+    /// material (`ChallengeMode::ChannelBound`) rather than fresh randomness, so the
+    /// post-handshake challenge derivation is actually exercised end to end.
+    fn test_phase3_linear_regression_one_data_with_channel_bound_attestation() {
+        let result = test_template::<LinearRegression>(
+            ONE_DATA_SOURCE_POLICY,
+            CLIENT_CERT,
+            CLIENT_KEY,
+            Some(LINEAR_REGRESSION_WASM),
+            &[(0, LINEAR_REGRESSION_DATA)],
+            &[],
+            true,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::ChannelBound,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_ok(), "error:{:?}", result);
     }
@@ -528,6 +1049,13 @@ mod tests {
             &[(0, PERSON_SET_1_DATA), (1, PERSON_SET_2_DATA)],
             &[],
             true,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_ok(), "error:{:?}", result);
     }
@@ -550,6 +1078,14 @@ mod tests {
                 (1, VEC_F64_2_DATA),
             ],
             true,
+            ExecutionStrategy::Interpreter,
+            // drive both stream packages of each round in a single frame
+            2,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_ok(), "error:{:?}", result);
     }
@@ -565,6 +1101,13 @@ mod tests {
             &[(0, SINGLE_F64_DATA)],
             &[(0, VEC_F64_1_DATA)],
             true,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -583,6 +1126,13 @@ mod tests {
                 (1, VEC_F64_2_DATA),
             ],
             true,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -602,6 +1152,13 @@ mod tests {
                 (2, VEC_F64_1_DATA),
             ],
             true,
+            ExecutionStrategy::Interpreter,
+            1,
+            ChallengeMode::Random,
+            0,
+            ClientKeyType::Auto,
+            None,
+            false,
         );
         assert!(result.is_err(), "An error should occur");
     }
@@ -613,6 +1170,7 @@ mod tests {
     /// computation: logistic regression, https://github.com/kimandrik/IDASH2017.
     /// data sources: idash2017/*.dat
     fn test_performance_idash2017_with_attestation() {
+        BENCH.lock().unwrap().clear();
         iterate_over_data(LOGISTICS_REGRESSION_DATA_PATH, |data_path| {
             info!("Data path: {}", data_path);
             let result = test_template::<(Vec<f64>, f64, f64)>(
@@ -624,9 +1182,17 @@ mod tests {
                 &[],
                 // turn on attestation
                 true,
+                ExecutionStrategy::Interpreter,
+                1,
+                ChallengeMode::Random,
+                0,
+                ClientKeyType::Auto,
+                None,
+                false,
             );
             assert!(result.is_ok(), "error:{:?}", result);
         });
+        report_benchmark("idash2017");
     }
 
     #[test]
@@ -636,6 +1202,7 @@ mod tests {
     /// computation: moving-average-convergence-divergence, https://github.com/woonhulktin/HETSA.
     /// data sources: macd/*.dat
     fn test_performance_macd_with_attestation() {
+        BENCH.lock().unwrap().clear();
         iterate_over_data(MACD_DATA_PATH, |data_path| {
             info!("Data path: {}", data_path);
             // call the test_template with info flag on,
@@ -649,9 +1216,17 @@ mod tests {
                 &[],
                 // turn on attestation
                 true,
+                ExecutionStrategy::Interpreter,
+                1,
+                ChallengeMode::Random,
+                0,
+                ClientKeyType::Auto,
+                None,
+                false,
             );
             assert!(result.is_ok(), "error:{:?}", result);
         });
+        report_benchmark("macd");
     }
 
     /// This test was written to test an issue.
@@ -678,6 +1253,13 @@ mod tests {
                 &[],
                 // turn on attestation
                 true,
+                ExecutionStrategy::Interpreter,
+                1,
+                ChallengeMode::Random,
+                0,
+                ClientKeyType::Auto,
+                None,
+                false,
             );
             assert!(result.is_ok(), "error:{:?}", result);
         });
@@ -690,6 +1272,7 @@ mod tests {
     /// computation: intersection-sum, matching the setting in .
     /// data sources: private-set-inter-sum/*.dat
     fn test_performance_set_intersection_sum_with_attestation() {
+        BENCH.lock().unwrap().clear();
         iterate_over_data("../test-collateral/private-set-inter-sum/", |data_path| {
             info!("Data path: {}", data_path);
             // call the test_template with info flag on,
@@ -703,9 +1286,17 @@ mod tests {
                 &[],
                 // turn on attestation
                 true,
+                ExecutionStrategy::Interpreter,
+                1,
+                ChallengeMode::Random,
+                0,
+                ClientKeyType::Auto,
+                None,
+                false,
             );
             assert!(result.is_ok(), "error:{:?}", result);
         });
+        report_benchmark("set-intersection-sum");
     }
 
     /// This is the template of test cases for sinaloa,
@@ -725,6 +1316,37 @@ mod tests {
         stream_id_paths: &[(u64, &str)],
         // if there is an attestation
         attestation_flag: bool,
+        // which execution backend to instantiate for this run; the engine is only
+        // built and logged, the computation itself always runs in the enclave,
+        // and no results are compared between backends
+        engine: ExecutionStrategy,
+        // maximum number of stream packages coalesced into a single provisioning
+        // frame; `1` reproduces the original one-handshake-per-package behaviour,
+        // larger values cut the per-round round-trips for big streaming datasets
+        stream_batch_size: usize,
+        // whether the attestation challenge is pure randomness or bound to the
+        // TLS channel's exported keying material
+        challenge_mode: ChallengeMode,
+        // number of extra reconnect rounds before provisioning: each re-runs the
+        // attestation step, which must be served from the attestation cache, and
+        // builds a new client session sharing the ticket store; those sessions
+        // never handshake, so TLS ticket resumption itself is not exercised;
+        // `0` reproduces the single-connection behaviour
+        resumes: usize,
+        // how the client key file should be decoded: PKCS#1 RSA, PKCS#8, or
+        // auto-detection, so tests can cover RSA and ECDSA P-256 identities
+        // rather than assuming an RSA one; an Ed25519 key parses but is
+        // rejected when the software signer is built
+        client_key_type: ClientKeyType,
+        // maximum TLS fragment size for this session; `None` keeps full-sized
+        // records, `Some(n)` caps records and `client_tls_send`'s per-write
+        // plaintext so large program/stream payloads are chunked instead of
+        // serialised into one giant record
+        max_fragment: Option<usize>,
+        // whether session teardown must confirm a graceful `close_notify` from the
+        // enclave; `false` keeps the historical best-effort shutdown, `true`
+        // surfaces `UncleanShutdown` on a truncated final response
+        check_close_notify: bool,
     ) -> Result<(), SinaloaError> {
         info!("### Step 0.  Initialise test configuration.");
         // initialise the pipe
@@ -743,10 +1365,19 @@ mod tests {
         let (policy, policy_json, policy_hash) = read_policy(policy_path)?;
         //let debug_flag = policy.debug;
         let ticket = setup(policy.tabasco_url().clone());
-        info!(
-            "             Setup time (μs): {}.",
-            time_setup.elapsed().as_micros()
-        );
+        record_phase("setup", time_setup);
+        // Instantiate the backend the strategy selects.  The wasmtime path yields
+        // a concrete engine here and the interpreter path yields `None`; either
+        // way the engine is not handed to the enclave, which runs the program on
+        // its hardwired `hcall` interpreter.
+        match engine.new_engine().map_err(|err| {
+            SinaloaError::DirectStrError(format!("could not build execution engine: {:?}", err))
+        })? {
+            Some(_wasmtime_engine) => {
+                info!("             Execution engine: {:?} (wasmtime backend).", engine)
+            }
+            None => info!("             Execution engine: {:?} (interpreter backend).", engine),
+        }
         info!("### Step 2.  Initialise enclave.");
         let time_init = Instant::now();
         let sinaloa = SinaloaEnclave::new(&policy_json)?;
@@ -757,41 +1388,146 @@ mod tests {
                 Ok(id)
             }
         })?;
-        let enclave_cert_hash = if attestation_flag {
-            attestation_flow(&policy.tabasco_url(), &policy.mexico_city_hash(), &sinaloa)?
-        } else {
+        // Shared across every session this run opens, so TLS tickets issued on
+        // the first connection are presented -- and resumed -- on later ones.
+        let session_store: Arc<dyn rustls::StoresClientSessions> =
+            Arc::new(rustls::ClientSessionMemoryCache::new(32));
+        let enclave_name = sinaloa.get_enclave_name()?;
+        // The enclave's self-signed cert hash is stable across reconnects and is
+        // the key under which an attestation ticket is cached.
+        let self_signed_hash = {
             let enclave_cert = enclave_self_signed_cert(&sinaloa)?;
             ring::digest::digest(&ring::digest::SHA256, enclave_cert.as_ref())
                 .as_ref()
                 .to_vec()
         };
 
-        info!("             Enclave generated a self-signed certificate:");
+        // The attestation challenge is either fresh randomness or, in
+        // channel-bound mode, derived from the TLS session's exported keying
+        // material so that a matched token cryptographically proves the attested
+        // enclave owns the very channel carrying the provisioning traffic --
+        // defeating token relay/MITM.  Results are cached by `attest_or_resume`,
+        // so a reconnecting client skips the token exchange.
+        let mut client_session;
+        // The attestation-pinned enclave hash.  The enclave still returns bare
+        // result bytes, so it feeds nothing on the result path yet; once the
+        // enclave emits `ResultEnvelope`s this is the attested identity handed to
+        // `sinaloa::result_envelope::verify_result_envelope`.
+        let _enclave_cert_hash = if attestation_flag {
+            match challenge_mode {
+                ChallengeMode::Random => {
+                    let challenge = rand::thread_rng().gen::<[u8; 32]>();
+                    let hash = attest_or_resume(
+                        &enclave_name,
+                        &self_signed_hash,
+                        &policy.tabasco_url(),
+                        &policy.mexico_city_hash(),
+                        &sinaloa,
+                        &challenge,
+                    )?;
+                    client_session = create_client_test_session(
+                        &sinaloa,
+                        client_cert_path,
+                        client_key_path,
+                        hash.clone(),
+                        session_store.clone(),
+                        client_key_type,
+                        max_fragment,
+                    )?;
+                    hash
+                }
+                ChallengeMode::ChannelBound => {
+                    // Pin to the enclave's self-signed certificate so the session
+                    // can be established first, bind the challenge to its keying
+                    // material once the handshake completes, then attest against
+                    // that channel.
+                    client_session = create_client_test_session(
+                        &sinaloa,
+                        client_cert_path,
+                        client_key_path,
+                        self_signed_hash.clone(),
+                        session_store.clone(),
+                        client_key_type,
+                        max_fragment,
+                    )?;
+                    // The exported keying material only exists once the TLS
+                    // handshake has completed, so pump the handshake flights
+                    // against the enclave here -- the server record loop is not
+                    // spawned until Step 3 -- before deriving the channel-bound
+                    // challenge from it.
+                    drive_handshake(&sinaloa, client_session_id, &mut client_session)?;
+                    let challenge = channel_bound_challenge(&mut client_session)?;
+                    attest_or_resume(
+                        &enclave_name,
+                        &self_signed_hash,
+                        &policy.tabasco_url(),
+                        &policy.mexico_city_hash(),
+                        &sinaloa,
+                        &challenge,
+                    )?
+                }
+            }
+        } else {
+            client_session = create_client_test_session(
+                &sinaloa,
+                client_cert_path,
+                client_key_path,
+                self_signed_hash.clone(),
+                session_store.clone(),
+                client_key_type,
+                max_fragment,
+            )?;
+            self_signed_hash.clone()
+        };
 
-        let mut client_session = create_client_test_session(
-            &sinaloa,
-            client_cert_path,
-            client_key_path,
-            enclave_cert_hash,
-        )?;
-        info!(
-            "             Initialasation time (μs): {}.",
-            time_init.elapsed().as_micros()
-        );
+        info!("             Enclave generated a self-signed certificate:");
+        record_phase("enclave_init", time_init);
+
+        // Exercise session resumption: reopen the session `resumes` times and
+        // check that, because the enclave's pinned cert hash is unchanged, the
+        // attestation ticket is resumed from the cache rather than re-fetched.
+        if attestation_flag {
+            for round in 0..resumes {
+                let before = attestation_exchange_count(&enclave_name, &self_signed_hash);
+                let challenge = rand::thread_rng().gen::<[u8; 32]>();
+                let _ = attest_or_resume(
+                    &enclave_name,
+                    &self_signed_hash,
+                    &policy.tabasco_url(),
+                    &policy.mexico_city_hash(),
+                    &sinaloa,
+                    &challenge,
+                )?;
+                let _resumed = create_client_test_session(
+                    &sinaloa,
+                    client_cert_path,
+                    client_key_path,
+                    self_signed_hash.clone(),
+                    session_store.clone(),
+                    client_key_type,
+                    max_fragment,
+                )?;
+                if attestation_exchange_count(&enclave_name, &self_signed_hash) != before {
+                    return Err(SinaloaError::DirectStrError(
+                        "resumed session unexpectedly re-ran attestation",
+                    ));
+                }
+                info!("### Resumption round #{} skipped attestation.", round);
+            }
+        }
 
         info!("### Step 3.  Spawn sinaloa server thread.");
         let time_server_boot = Instant::now();
         CONTINUE_FLAG_HASH.lock()?.insert(ticket, true);
+        MAX_FRAGMENT.lock()?.insert(ticket, max_fragment);
+        CHECK_CLOSE_NOTIFY.lock()?.insert(ticket, check_close_notify);
         let server_loop_handle = thread::spawn(move || {
             server_tls_loop(&sinaloa, server_tls_tx, server_tls_rx, ticket).map_err(|e| {
                 CONTINUE_FLAG_HASH.lock().unwrap().insert(ticket, false);
                 e
             })
         });
-        info!(
-            "             Booting sinaloa server time (μs): {}.",
-            time_server_boot.elapsed().as_micros()
-        );
+        record_phase("server_boot", time_server_boot);
 
         // Need to clone paths to concreate strings,
         // so the ownership can be transferred into a client thread.
@@ -814,6 +1550,16 @@ mod tests {
         // However if an Error pop up, the thread set the CONTINUE_FLAG to false,
         // hence stopping the server thread.
         let client_body = move || {
+            // Everything the client drives from here on goes through one transport
+            // that owns the session and its record channels, rather than threading
+            // the raw mpsc ends through every call.
+            let mut transport = MpscTlsTransport {
+                session_id: client_session_id,
+                ticket,
+                tx: client_tls_tx,
+                rx: client_tls_rx,
+                session: client_session,
+            };
             info!(
                 "### Step 4.  Client provisions program at {:?}.",
                 program_path
@@ -821,56 +1567,22 @@ mod tests {
             // if there is a program provided
             if let Some(path) = program_path {
                 let time_provosion_data = Instant::now();
-                check_enclave_state(
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                    ENCLAVE_STATE_INITIAL,
-                )?;
-                check_policy_hash(
-                    &policy_hash,
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                )?;
-                let response = provision_program(
-                    path.as_str(),
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                )?;
+                check_enclave_state(&mut transport, ENCLAVE_STATE_INITIAL)?;
+                check_policy_hash(&policy_hash, &mut transport)?;
+                let response = transport.provision_program(path.as_str())?;
                 info!(
                     "             Client received acknowledgement after sending program: {:?}",
                     colima::parse_mexico_city_response(&response)
                 );
-                info!(
-                    "             Provisioning program time (μs): {}.",
-                    time_provosion_data.elapsed().as_micros()
-                );
+                record_phase("program_provision", time_provosion_data);
                 info!("### Step 5.  Program provider requests program hash.");
                 let time_hash = Instant::now();
-                let _response = request_program_hash(
-                    policy.pi_hash().as_str(),
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                )?;
+                let _response = request_program_hash(policy.pi_hash().as_str(), &mut transport)?;
                 info!(
                     "             Client received installed program hash data: {:?}",
                     colima::parse_mexico_city_response(&response)
                 );
-                info!(
-                    "             Program provider hash response time (μs): {}.",
-                    time_hash.elapsed().as_micros()
-                );
+                record_phase("program_hash", time_hash);
             }
 
             info!("### Step 6.  Data providers provision secret data.");
@@ -880,52 +1592,17 @@ mod tests {
                     package_id
                 );
                 let time_data_hash = Instant::now();
-                check_enclave_state(
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                    ENCLAVE_STATE_DATA_SOURCES_LOADING,
-                )?;
-                let _response = request_program_hash(
-                    policy.pi_hash().as_str(),
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                )?;
-                check_policy_hash(
-                    &policy_hash,
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                )?;
-                info!(
-                    "             Data provider hash response time (μs): {}.",
-                    time_data_hash.elapsed().as_micros()
-                );
+                check_enclave_state(&mut transport, ENCLAVE_STATE_DATA_SOURCES_LOADING)?;
+                let _response = request_program_hash(policy.pi_hash().as_str(), &mut transport)?;
+                check_policy_hash(&policy_hash, &mut transport)?;
+                record_phase("data_hash", time_data_hash);
                 let time_data = Instant::now();
-                let response = provision_data(
-                    data_path.as_str(),
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                    *package_id,
-                )?;
+                let response = transport.provision_data(data_path.as_str(), *package_id)?;
                 info!(
                     "             Client received acknowledgement after sending data: {:?},",
                     colima::parse_mexico_city_response(&response)
                 );
-                info!(
-                    "             Provisioning data time (μs): {}.",
-                    time_data.elapsed().as_micros()
-                );
+                record_phase("data_provision", time_data);
             }
             // If stream_id_paths is NOT empty, we are in streaming mode
             if !stream_id_paths.is_empty() {
@@ -968,100 +1645,43 @@ mod tests {
                     };
                     info!("------------ Streaming Round # {} ------------", count);
                     count += 1;
-                    for (package_id, data) in next_round_data.iter() {
+                    // Send this round's packages in batches of up to
+                    // `stream_batch_size`, validating policy/program hash once per
+                    // batch rather than once per package.  `next_round_data` is
+                    // already in `id_vec` order, so chunking preserves the
+                    // per-package ordering semantics the enclave expects.
+                    for batch in next_round_data.chunks(stream_batch_size.max(1)) {
                         let time_stream_hash = Instant::now();
-                        check_enclave_state(
-                            client_session_id,
-                            &mut client_session,
-                            ticket,
-                            &client_tls_tx,
-                            &client_tls_rx,
-                            ENCLAVE_STATE_STREAM_SOURCE_SLOADING,
-                        )?;
-                        let _response = request_program_hash(
-                            policy.pi_hash().as_str(),
-                            client_session_id,
-                            &mut client_session,
-                            ticket,
-                            &client_tls_tx,
-                            &client_tls_rx,
-                        )?;
-                        check_policy_hash(
-                            &policy_hash,
-                            client_session_id,
-                            &mut client_session,
-                            ticket,
-                            &client_tls_tx,
-                            &client_tls_rx,
-                        )?;
+                        check_enclave_state(&mut transport, ENCLAVE_STATE_STREAM_SOURCE_SLOADING)?;
+                        let _response =
+                            request_program_hash(policy.pi_hash().as_str(), &mut transport)?;
+                        check_policy_hash(&policy_hash, &mut transport)?;
+                        record_phase("stream_hash", time_stream_hash);
                         info!(
-                            "             Stream provider hash response time (μs): {}.",
-                            time_stream_hash.elapsed().as_micros()
-                        );
-                        info!(
-                            "             Stream provider provision secret data #{}.",
-                            package_id
+                            "             Stream provider provision secret data batch of {} package(s): {:?}.",
+                            batch.len(),
+                            batch.iter().map(|(id, _)| *id).collect::<Vec<_>>()
                         );
                         let time_stream = Instant::now();
-                        let response = provision_stream(
-                            data.as_slice(),
-                            client_session_id,
-                            &mut client_session,
-                            ticket,
-                            &client_tls_tx,
-                            &client_tls_rx,
-                            *package_id,
-                        )?;
+                        let response = transport.provision_stream_batch(batch)?;
                         info!(
-                            "             Stream provider received acknowledgement after sending stream data: {:?},",
+                            "             Stream provider received acknowledgement after sending stream batch: {:?},",
                             colima::parse_mexico_city_response(&response)
                         );
-                        info!(
-                            "             Provisioning stream time (μs): {}.",
-                            time_stream.elapsed().as_micros()
-                        );
+                        record_phase("stream_round", time_stream);
                     }
                     info!("### Step 8.  Result retrievers request program.");
                     let time_result_hash = Instant::now();
-                    check_enclave_state(
-                        client_session_id,
-                        &mut client_session,
-                        ticket,
-                        &client_tls_tx,
-                        &client_tls_rx,
-                        ENCLAVE_STATE_READY_TO_EXECUTE,
-                    )?;
-                    let _response = request_program_hash(
-                        policy.pi_hash().as_str(),
-                        client_session_id,
-                        &mut client_session,
-                        ticket,
-                        &client_tls_tx,
-                        &client_tls_rx,
-                    )?;
-                    check_policy_hash(
-                        &policy_hash,
-                        client_session_id,
-                        &mut client_session,
-                        ticket,
-                        &client_tls_tx,
-                        &client_tls_rx,
-                    )?;
-                    info!(
-                        "             Result retriever hash response time (μs): {}.",
-                        time_result_hash.elapsed().as_micros()
-                    );
+                    check_enclave_state(&mut transport, ENCLAVE_STATE_READY_TO_EXECUTE)?;
+                    let _response =
+                        request_program_hash(policy.pi_hash().as_str(), &mut transport)?;
+                    check_policy_hash(&policy_hash, &mut transport)?;
+                    record_phase("result_hash", time_result_hash);
                     let time_result = Instant::now();
                     info!("             Result retrievers request result.");
-                    let response = client_tls_send(
-                        &client_tls_tx,
-                        &client_tls_rx,
-                        client_session_id,
-                        &mut client_session,
-                        ticket,
-                        &colima::serialize_request_result()?.as_slice(),
-                    )
-                    .and_then(|response| {
+                    let response = transport
+                        .round_trip(&colima::serialize_request_result()?.as_slice())
+                        .and_then(|response| {
                         // decode the result
                         let response = colima::parse_mexico_city_response(&response)?;
                         let response = colima::parse_result(&response)?;
@@ -1069,24 +1689,15 @@ mod tests {
                             "Result retrievers response",
                         ))
                     })?;
-                    info!(
-                        "             Computation result time (μs): {}.",
-                        time_result.elapsed().as_micros()
-                    );
+                    record_phase("result_retrieval", time_result);
                     info!("### Step 9.  Client decodes the result.");
-                    let result: T = pinecone::from_bytes(&response.as_slice())?;
+                    let result: T = pinecone::from_bytes(response.as_slice())?;
                     info!("             Client received result: {:?},", result);
                     // there are more streaming data, requesting next round
                     if stream_data_vec.iter().map(|d| !d.is_empty()).all(|d| d) {
                         info!("             Client request next round");
-                        let _response = client_tls_send(
-                            &client_tls_tx,
-                            &client_tls_rx,
-                            client_session_id,
-                            &mut client_session,
-                            ticket,
-                            &colima::serialize_request_next_round()?.as_slice(),
-                        )?;
+                        let _response = transport
+                            .round_trip(&colima::serialize_request_next_round()?.as_slice())?;
                     }
                 }
                 info!("------------ Stream-Result-Next End  ------------");
@@ -1094,45 +1705,15 @@ mod tests {
                 info!("### Step 7.  NOT in streaming mode.");
                 info!("### Step 8.  Result retrievers request program.");
                 let time_result_hash = Instant::now();
-                check_enclave_state(
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                    ENCLAVE_STATE_READY_TO_EXECUTE,
-                )?;
-                let _response = request_program_hash(
-                    policy.pi_hash().as_str(),
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                )?;
-                check_policy_hash(
-                    &policy_hash,
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &client_tls_tx,
-                    &client_tls_rx,
-                )?;
-                info!(
-                    "             Result retriever hash response time (μs): {}.",
-                    time_result_hash.elapsed().as_micros()
-                );
+                check_enclave_state(&mut transport, ENCLAVE_STATE_READY_TO_EXECUTE)?;
+                let _response = request_program_hash(policy.pi_hash().as_str(), &mut transport)?;
+                check_policy_hash(&policy_hash, &mut transport)?;
+                record_phase("result_hash", time_result_hash);
                 let time_result = Instant::now();
                 info!("             Result retrievers request result.");
-                let response = client_tls_send(
-                    &client_tls_tx,
-                    &client_tls_rx,
-                    client_session_id,
-                    &mut client_session,
-                    ticket,
-                    &colima::serialize_request_result()?.as_slice(),
-                )
-                .and_then(|response| {
+                let response = transport
+                    .round_trip(&colima::serialize_request_result()?.as_slice())
+                    .and_then(|response| {
                     // decode the result
                     let response = colima::parse_mexico_city_response(&response)?;
                     let response = colima::parse_result(&response)?;
@@ -1140,54 +1721,51 @@ mod tests {
                         "Result retrievers response",
                     ))
                 })?;
-                info!(
-                    "             Computation result time (μs): {}.",
-                    time_result.elapsed().as_micros()
-                );
+                record_phase("result_retrieval", time_result);
 
                 info!("### Step 9.  Client decodes the result.");
-                let result: T = pinecone::from_bytes(&response.as_slice())?;
+                let result: T = pinecone::from_bytes(response.as_slice())?;
                 info!("             Client received result: {:?},", result);
             }
 
             info!("### Step 10. Client shuts down Veracruz.");
             let time_shutdown = Instant::now();
-            check_enclave_state(
-                client_session_id,
-                &mut client_session,
-                ticket,
-                &client_tls_tx,
-                &client_tls_rx,
-                ENCLAVE_STATE_FINISHED_EXECUTING,
-            )?;
-            let response = client_tls_send(
-                &client_tls_tx,
-                &client_tls_rx,
-                client_session_id,
-                &mut client_session,
-                ticket,
-                &colima::serialize_request_shutdown()?.as_slice(),
-            )?;
+            check_enclave_state(&mut transport, ENCLAVE_STATE_FINISHED_EXECUTING)?;
+            let response =
+                transport.round_trip(&colima::serialize_request_shutdown()?.as_slice())?;
             info!(
                 "             Client received acknowledgment after shutdown request: {:?}",
                 colima::parse_mexico_city_response(&response)
             );
-            info!(
-                "             Shutdown time (μs): {}.",
-                time_shutdown.elapsed().as_micros()
-            );
+            record_phase("shutdown", time_shutdown);
+
+            // Confirm the enclave tore the session down cleanly rather than having
+            // its final response truncated.
+            if *CHECK_CLOSE_NOTIFY.lock()?.get(&ticket).unwrap_or(&false) {
+                transport.close_notify()?;
+            }
             Ok::<(), SinaloaError>(())
         };
 
-        thread::spawn(move || {
+        let client_result = thread::spawn(move || {
             client_body().map_err(|e| {
                 CONTINUE_FLAG_HASH.lock().unwrap().insert(ticket, false);
                 e
             })
         })
         .join()
-        // double `?` one for join and one for client_body
-        .map_err(|e| SinaloaError::JoinError(e))??;
+        .map_err(|e| SinaloaError::JoinError(e))?;
+
+        // Feed the session outcome into the reputation table: an authentication or
+        // TLS failure penalises the peer, and enough of them drive it past the ban
+        // threshold so `create_client_test_session` starts refusing its handshake.
+        if let Ok(client_cert) = read_cert_file(client_cert_path) {
+            let fingerprint = client_fingerprint(&client_cert);
+            if client_result.is_err() {
+                REPUTATION.lock()?.record_failure(&fingerprint);
+            }
+        }
+        client_result?;
 
         // double `?` one for join and one for client_body
         server_loop_handle
@@ -1276,47 +1854,208 @@ mod tests {
         })
     }
 
-    fn provision_program(
-        filename: &str,
-        client_session_id: u32,
-        client_session: &mut dyn rustls::Session,
+    /// A session transport carrying one colima request to the enclave and its
+    /// response back.
+    ///
+    /// Provisioning and the session-control exchanges are written against this
+    /// trait rather than against the mpsc channels directly, so the concrete
+    /// carrier is a detail of the implementation.  [`MpscTlsTransport`] is the
+    /// in-process backend: TLS records shuttled over `std::sync::mpsc` frames, as
+    /// the harness has always used.  [`QuicTransport`] is the other backend -- a
+    /// real QUIC connection that carries each colima request on its own
+    /// bidirectional stream of one attested connection; requests still go one at
+    /// a time, since each `round_trip` waits for its response.  It is compiled under the `quic`
+    /// feature because it pulls in the QUIC endpoint stack the default in-process
+    /// test build does not need.
+    trait Transport {
+        /// Send one request and return the enclave's response bytes.
+        fn round_trip(&mut self, request: &[u8]) -> Result<Vec<u8>, SinaloaError>;
+
+        /// Close the session gracefully, verifying the peer's `close_notify`.
+        fn close_notify(&mut self) -> Result<(), SinaloaError>;
+
+        /// Provision the program at `filename`.
+        fn provision_program(&mut self, filename: &str) -> Result<Vec<u8>, SinaloaError> {
+            let mut program_file = std::fs::File::open(filename)?;
+            let mut program_text = std::vec::Vec::new();
+            program_file.read_to_end(&mut program_text)?;
+            let serialized_program_text = colima::serialize_program(&program_text)?;
+            self.round_trip(&serialized_program_text[..])
+        }
+
+        /// Provision one static data package read from `filename`.
+        fn provision_data(
+            &mut self,
+            filename: &str,
+            package_id: u64,
+        ) -> Result<Vec<u8>, SinaloaError> {
+            let data = {
+                let mut data_file = std::fs::File::open(filename)?;
+                let mut data_buffer = std::vec::Vec::new();
+                data_file.read_to_end(&mut data_buffer)?;
+                data_buffer
+            };
+            let serialized_data = colima::serialize_program_data(&data, package_id as u32)?;
+            self.round_trip(&serialized_data[..])
+        }
+
+        /// Provision a whole batch of stream packages in a single round-trip.
+        ///
+        /// The entries are sent in the order given, which the caller has already
+        /// put in `id_vec` order, so the enclave ingests them with the same
+        /// per-package semantics as one-package-at-a-time provisioning -- only
+        /// without the intervening handshake per package.  A single-element batch
+        /// reproduces the original per-package behaviour exactly.
+        fn provision_stream_batch(
+            &mut self,
+            batch: &[(u64, Vec<u8>)],
+        ) -> Result<Vec<u8>, SinaloaError> {
+            let entries: Vec<(u32, &[u8])> = batch
+                .iter()
+                .map(|(package_id, data)| (*package_id as u32, data.as_slice()))
+                .collect();
+            let serialized_stream_batch = colima::serialize_stream_batch(&entries)?;
+            self.round_trip(&serialized_stream_batch[..])
+        }
+    }
+
+    /// The in-process transport: opaque TLS records moved over mpsc channels.
+    struct MpscTlsTransport {
+        session_id: u32,
         ticket: u32,
-        client_tls_tx: &std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
-        client_tls_rx: &std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
-    ) -> Result<Vec<u8>, SinaloaError> {
-        let mut program_file = std::fs::File::open(filename)?;
-        let mut program_text = std::vec::Vec::new();
-
-        program_file.read_to_end(&mut program_text)?;
-
-        let serialized_program_text = colima::serialize_program(&program_text)?;
-        client_tls_send(
-            client_tls_tx,
-            client_tls_rx,
-            client_session_id,
-            client_session,
-            ticket,
-            &serialized_program_text[..],
-        )
+        tx: std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
+        rx: std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
+        session: rustls::ClientSession,
+    }
+
+    impl Transport for MpscTlsTransport {
+        fn round_trip(&mut self, request: &[u8]) -> Result<Vec<u8>, SinaloaError> {
+            client_tls_send(
+                &self.tx,
+                &self.rx,
+                self.session_id,
+                &mut self.session,
+                self.ticket,
+                request,
+            )
+        }
+
+        fn close_notify(&mut self) -> Result<(), SinaloaError> {
+            client_close_notify(
+                self.session_id,
+                &mut self.session,
+                self.ticket,
+                &self.tx,
+                &self.rx,
+            )
+        }
+    }
+
+    /// The QUIC transport: one attested connection, one bidirectional stream per
+    /// colima request.
+    ///
+    /// The handshake binds the attested enclave-certificate hash through the very
+    /// same [`build_client_config`] the mpsc/TLS backend uses -- `quinn` takes a
+    /// rustls `ClientConfig`, so the pinned-hash verifier and the client identity
+    /// carry over unchanged.  Each `round_trip` opens a fresh bidirectional stream
+    /// and blocks until that stream's response has been read, so requests made
+    /// through this transport do not run concurrently.  Because
+    /// the trait is synchronous, the async endpoint is driven on a current-thread
+    /// runtime owned by the transport.
+    #[cfg(feature = "quic")]
+    struct QuicTransport {
+        runtime: tokio::runtime::Runtime,
+        connection: quinn::Connection,
+    }
+
+    #[cfg(feature = "quic")]
+    impl QuicTransport {
+        /// Dial the enclave over QUIC, reusing the shared rustls client config so
+        /// the attested-hash pinning and client authentication match the TLS path.
+        fn connect(
+            sinaloa: &dyn sinaloa::Sinaloa,
+            client_cert_filename: &str,
+            client_key_filename: &str,
+            cert_hash: Vec<u8>,
+            key_type: ClientKeyType,
+            server_addr: std::net::SocketAddr,
+        ) -> Result<Self, SinaloaError> {
+            let client_config =
+                build_client_config(client_cert_filename, client_key_filename, cert_hash, key_type)?;
+            let enclave_name = sinaloa.get_enclave_name()?;
+
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let connection = runtime.block_on(async {
+                let mut endpoint =
+                    quinn::Endpoint::client("0.0.0.0:0".parse().map_err(|_| {
+                        SinaloaError::MissingFieldError("quic client bind address")
+                    })?)?;
+                endpoint.set_default_client_config(quinn::ClientConfig::new(std::sync::Arc::new(
+                    client_config,
+                )));
+                let connecting = endpoint
+                    .connect(server_addr, enclave_name.as_str())
+                    .map_err(|err| {
+                        SinaloaError::DirectStrError(format!("quic connect: {:?}", err))
+                    })?;
+                let connection = connecting.await.map_err(|err| {
+                    SinaloaError::DirectStrError(format!("quic handshake: {:?}", err))
+                })?;
+                Ok::<quinn::Connection, SinaloaError>(connection)
+            })?;
+
+            Ok(QuicTransport {
+                runtime,
+                connection,
+            })
+        }
+    }
+
+    #[cfg(feature = "quic")]
+    impl Transport for QuicTransport {
+        fn round_trip(&mut self, request: &[u8]) -> Result<Vec<u8>, SinaloaError> {
+            let connection = self.connection.clone();
+            self.runtime.block_on(async {
+                let (mut send, mut recv) = connection.open_bi().await.map_err(|err| {
+                    SinaloaError::DirectStrError(format!("quic open stream: {:?}", err))
+                })?;
+                send.write_all(request).await.map_err(|err| {
+                    SinaloaError::DirectStrError(format!("quic stream write: {:?}", err))
+                })?;
+                // Signalling the end of this request's stream lets the enclave read
+                // it as a whole colima message and reply on the same stream.
+                send.finish().await.map_err(|err| {
+                    SinaloaError::DirectStrError(format!("quic stream finish: {:?}", err))
+                })?;
+                let response = recv.read_to_end(MAX_QUIC_RESPONSE).await.map_err(|err| {
+                    SinaloaError::DirectStrError(format!("quic stream read: {:?}", err))
+                })?;
+                Ok::<std::vec::Vec<u8>, SinaloaError>(response)
+            })
+        }
+
+        fn close_notify(&mut self) -> Result<(), SinaloaError> {
+            // QUIC carries the TLS close through the connection close frame; there
+            // is no separate record to flush as there is on the mpsc pipe.
+            self.connection
+                .close(0u32.into(), b"veracruz-session-complete");
+            Ok(())
+        }
     }
 
+    /// Upper bound on a single QUIC response, matching the enclave's largest
+    /// colima reply (a Merkle-rooted result) with headroom.
+    #[cfg(feature = "quic")]
+    const MAX_QUIC_RESPONSE: usize = 32 * 1024 * 1024;
+
     fn check_policy_hash(
         expected_policy_hash: &str,
-        client_session_id: u32,
-        client_session: &mut dyn rustls::Session,
-        ticket: u32,
-        client_tls_tx: &std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
-        client_tls_rx: &std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
+        transport: &mut dyn Transport,
     ) -> Result<(), SinaloaError> {
         let serialized_request_policy_hash = colima::serialize_request_policy_hash()?;
-        let response = client_tls_send(
-            client_tls_tx,
-            client_tls_rx,
-            client_session_id,
-            client_session,
-            ticket,
-            &serialized_request_policy_hash[..],
-        )?;
+        let response = transport.round_trip(&serialized_request_policy_hash[..])?;
         let parsed_response = colima::parse_mexico_city_response(&response)?;
         let status = parsed_response.get_status();
         if status != colima::ResponseStatus::SUCCESS {
@@ -1339,21 +2078,10 @@ mod tests {
 
     fn request_program_hash(
         expected_program_hash: &str,
-        client_session_id: u32,
-        client_session: &mut dyn rustls::Session,
-        ticket: u32,
-        client_tls_tx: &std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
-        client_tls_rx: &std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
+        transport: &mut dyn Transport,
     ) -> Result<bool, SinaloaError> {
         let serialized_pi_hash_request = colima::serialize_request_pi_hash()?;
-        let data = client_tls_send(
-            client_tls_tx,
-            client_tls_rx,
-            client_session_id,
-            client_session,
-            ticket,
-            &serialized_pi_hash_request[..],
-        )?;
+        let data = transport.round_trip(&serialized_pi_hash_request[..])?;
         let parsed_response = colima::parse_mexico_city_response(&data)?;
         let status = parsed_response.get_status();
         match status {
@@ -1378,89 +2106,17 @@ mod tests {
     }
 
     fn request_enclave_state(
-        client_session_id: u32,
-        client_session: &mut dyn rustls::Session,
-        ticket: u32,
-        client_tls_tx: &std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
-        client_tls_rx: &std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
+        transport: &mut dyn Transport,
     ) -> Result<Vec<u8>, SinaloaError> {
         let serialized_enclave_state_request = colima::serialize_request_enclave_state()?;
-
-        client_tls_send(
-            client_tls_tx,
-            client_tls_rx,
-            client_session_id,
-            client_session,
-            ticket,
-            serialized_enclave_state_request.as_slice(),
-        )
-    }
-
-    fn provision_data(
-        filename: &str,
-        client_session_id: u32,
-        client_session: &mut rustls::ClientSession,
-        ticket: u32,
-        client_tls_tx: &std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
-        client_tls_rx: &std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
-        package_id: u64,
-    ) -> Result<Vec<u8>, SinaloaError> {
-        // The client also sends the associated data
-        let data = {
-            let mut data_file = std::fs::File::open(filename)?;
-            let mut data_buffer = std::vec::Vec::new();
-            data_file.read_to_end(&mut data_buffer)?;
-            data_buffer
-        };
-        let serialized_data = colima::serialize_program_data(&data, package_id as u32)?;
-
-        client_tls_send(
-            client_tls_tx,
-            client_tls_rx,
-            client_session_id,
-            client_session,
-            ticket,
-            &serialized_data[..],
-        )
-    }
-
-    fn provision_stream(
-        data: &[u8],
-        client_session_id: u32,
-        client_session: &mut rustls::ClientSession,
-        ticket: u32,
-        client_tls_tx: &std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
-        client_tls_rx: &std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
-        package_id: u64,
-    ) -> Result<Vec<u8>, SinaloaError> {
-        // The client also sends the associated data
-        let serialized_stream = colima::serialize_stream(data, package_id as u32)?;
-
-        client_tls_send(
-            client_tls_tx,
-            client_tls_rx,
-            client_session_id,
-            client_session,
-            ticket,
-            &serialized_stream[..],
-        )
+        transport.round_trip(serialized_enclave_state_request.as_slice())
     }
 
     fn check_enclave_state(
-        client_session_id: u32,
-        client_session: &mut dyn rustls::Session,
-        ticket: u32,
-        client_tls_tx: &std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
-        client_tls_rx: &std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
+        transport: &mut dyn Transport,
         expecting: u8,
     ) -> Result<(), SinaloaError> {
-        let encoded_state = request_enclave_state(
-            client_session_id,
-            client_session,
-            ticket,
-            client_tls_tx,
-            client_tls_rx,
-        )?;
+        let encoded_state = request_enclave_state(transport)?;
         let parsed = colima::parse_mexico_city_response(&encoded_state)?;
 
         if parsed.has_state() {
@@ -1507,6 +2163,12 @@ mod tests {
                 }
             }
         }
+        // The flag flipped before the enclave signalled a clean close.  When the
+        // close-notify check is armed that is a truncated teardown, not merely a
+        // missing message, so surface it as such.
+        if *CHECK_CLOSE_NOTIFY.lock()?.get(&ticket).unwrap_or(&false) {
+            return Err(SinaloaError::UncleanShutdown);
+        }
         Err(SinaloaError::DirectStrError("No message arrives server"))
     }
 
@@ -1518,13 +2180,30 @@ mod tests {
         ticket: u32,
         send_data: &[u8],
     ) -> Result<Vec<u8>, SinaloaError> {
-        session.write_all(&send_data)?;
-
-        let mut output: std::vec::Vec<u8> = std::vec::Vec::new();
-
-        session.write_tls(&mut output)?;
-
-        tx.send((session_id, output))?;
+        // Feed the request to the session in fragment-sized chunks, flushing the
+        // encrypted records after each write.  Without a cap the whole payload is
+        // serialised into one record and buffered at once; chunking keeps both the
+        // record and the in-memory `output` buffer bounded for large programs and
+        // streams, while the enclave reassembles the fragmented colima message.
+        let fragment = MAX_FRAGMENT
+            .lock()?
+            .get(&ticket)
+            .cloned()
+            .unwrap_or(None);
+        let chunk_size = fragment.unwrap_or(send_data.len()).max(1);
+        if send_data.is_empty() {
+            // Flush even an empty request so the handshake records still travel.
+            let mut output: std::vec::Vec<u8> = std::vec::Vec::new();
+            session.write_tls(&mut output)?;
+            tx.send((session_id, output))?;
+        } else {
+            for chunk in send_data.chunks(chunk_size) {
+                session.write_all(chunk)?;
+                let mut output: std::vec::Vec<u8> = std::vec::Vec::new();
+                session.write_tls(&mut output)?;
+                tx.send((session_id, output))?;
+            }
+        }
 
         while *CONTINUE_FLAG_HASH
             .lock()?
@@ -1557,32 +2236,138 @@ mod tests {
         ))
     }
 
+    /// Close a session gracefully and verify the peer did the same.
+    ///
+    /// Sends this side's `close_notify`, then waits for the enclave's: a matching
+    /// alert surfaces as a clean end-of-stream from `read_to_end`, whereas a
+    /// truncated teardown returns `ErrorKind::UnexpectedEof`.  Distinguishing the
+    /// two is what lets a truncation attack on the final provisioning response be
+    /// told apart from an ordinary shutdown.
+    fn client_close_notify(
+        session_id: u32,
+        session: &mut dyn rustls::Session,
+        ticket: u32,
+        tx: &std::sync::mpsc::Sender<(u32, std::vec::Vec<u8>)>,
+        rx: &std::sync::mpsc::Receiver<std::vec::Vec<u8>>,
+    ) -> Result<(), SinaloaError> {
+        session.send_close_notify();
+        let mut output: std::vec::Vec<u8> = std::vec::Vec::new();
+        session.write_tls(&mut output)?;
+        tx.send((session_id, output))?;
+
+        while *CONTINUE_FLAG_HASH
+            .lock()?
+            .get(&ticket)
+            .ok_or(SinaloaError::MissingFieldError("CONTINUE_FLAG_HASH ticket"))?
+        {
+            if let Ok(received) = rx.try_recv() {
+                let mut slice = &received[..];
+                session.read_tls(&mut slice)?;
+                session.process_new_packets()?;
+
+                let mut drained: std::vec::Vec<u8> = std::vec::Vec::new();
+                match session.read_to_end(&mut drained) {
+                    // A clean end-of-stream means the peer's `close_notify` arrived.
+                    Ok(_) => return Ok(()),
+                    Err(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                        return Err(SinaloaError::UncleanShutdown)
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        // The session was torn down before the enclave's `close_notify` arrived.
+        Err(SinaloaError::UncleanShutdown)
+    }
+
     fn create_client_test_session(
         sinaloa: &dyn sinaloa::Sinaloa,
         client_cert_filename: &str,
         client_key_filename: &str,
         cert_hash: Vec<u8>,
+        // Shared across reconnects so tickets issued on the first connection are
+        // presented on later ones, letting the handshake resume instead of
+        // starting fresh.
+        session_store: Arc<dyn rustls::StoresClientSessions>,
+        // Preferred encoding for the on-disk client key, so a policy pinning an
+        // ECDSA P-256 identity can be exercised; Ed25519 keys are not supported
+        // by the software signer.
+        key_type: ClientKeyType,
+        // Caps the negotiated TLS record size so multi-megabyte programs are split
+        // across records rather than emitted as one; `None` keeps the default.
+        max_fragment: Option<usize>,
     ) -> Result<rustls::ClientSession, SinaloaError> {
+        let mut client_config =
+            build_client_config(client_cert_filename, client_key_filename, cert_hash, key_type)?;
+        client_config.set_persistence(session_store);
+        client_config.set_mtu(&max_fragment);
+
+        let enclave_name = sinaloa.get_enclave_name()?;
+        let dns_name = webpki::DNSNameRef::try_from_ascii_str(enclave_name.as_str())?;
+        Ok(rustls::ClientSession::new(
+            &std::sync::Arc::new(client_config),
+            dns_name,
+        ))
+    }
+
+    /// Build the rustls `ClientConfig` shared by every Veracruz session regardless
+    /// of carrier: the authorising client certificate and its signing key, the
+    /// pinned attested enclave-certificate hash, and the server trust anchors.  The
+    /// TLS-over-mpsc and QUIC transports both start from this, so a new carrier adds
+    /// no duplicated certificate/key/reputation plumbing -- it only layers its own
+    /// persistence/MTU (TLS) or endpoint (QUIC) on top.
+    fn build_client_config(
+        client_cert_filename: &str,
+        client_key_filename: &str,
+        cert_hash: Vec<u8>,
+        key_type: ClientKeyType,
+    ) -> Result<rustls::ClientConfig, SinaloaError> {
         let client_cert = read_cert_file(client_cert_filename)?;
 
-        let client_priv_key = read_priv_key_file(client_key_filename)?;
+        // Refuse the handshake outright for peers the reputation table has banned,
+        // rather than letting them retry a doomed authentication forever.
+        let fingerprint = client_fingerprint(&client_cert);
+        if !REPUTATION.lock()?.is_allowed(&fingerprint) {
+            return Err(SinaloaError::PeerBanned(fingerprint));
+        }
 
         let mut client_config = rustls::ClientConfig::new_self_signed();
         let mut client_cert_vec = std::vec::Vec::new();
         client_cert_vec.push(client_cert);
-        client_config.set_single_client_cert(client_cert_vec, client_priv_key);
+        // A key may be referenced either as an on-disk PEM file or, for deployments
+        // keeping the authorising key in a hardware token, as a PKCS#11 URI wired
+        // into rustls through `Pkcs11Signer`, which in this build fails every
+        // signing request.
+        match sinaloa::signer::KeyReference::parse(client_key_filename) {
+            sinaloa::signer::KeyReference::File(path) => {
+                // Route the on-disk key through the same `Signer`/`SigningKey`
+                // delegation as the token path, so RSA and ECDSA identities are
+                // signed and advertised correctly rather than via rustls' default
+                // RSA assumption.
+                let client_priv_key = read_priv_key_file(&path, key_type)?;
+                let signer =
+                    std::sync::Arc::new(sinaloa::signer::SoftwareSigner::from_der(&client_priv_key.0)?);
+                let signing_key = sinaloa::signer::delegated_signing_key(signer);
+                client_config
+                    .set_single_client_cert_with_signing_key(client_cert_vec, signing_key);
+            }
+            sinaloa::signer::KeyReference::Pkcs11(uri) => {
+                let signer = std::sync::Arc::new(sinaloa::signer::Pkcs11Signer::open(&uri)?);
+                let signing_key = sinaloa::signer::delegated_signing_key(signer);
+                client_config
+                    .set_single_client_cert_with_signing_key(client_cert_vec, signing_key);
+            }
+        }
         client_config
             .root_store
             .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
-
         client_config.pinned_cert_hashes.push(cert_hash);
 
-        let enclave_name = sinaloa.get_enclave_name()?;
-        let dns_name = webpki::DNSNameRef::try_from_ascii_str(enclave_name.as_str())?;
-        Ok(rustls::ClientSession::new(
-            &std::sync::Arc::new(client_config),
-            dns_name,
-        ))
+        // A well-formed, authorised session nudges the peer's score back toward
+        // healthy; TLS/auth failures apply `record_failure` and, past the ban
+        // threshold, the gate above starts refusing the handshake.
+        REPUTATION.lock()?.record_success(&fingerprint);
+        Ok(client_config)
     }
 
     fn post_buffer(url: &str, data: &str) -> Result<String, SinaloaError> {
@@ -1638,14 +2423,59 @@ mod tests {
         Ok(received_body)
     }
 
+    /// Drive a freshly created client session's TLS handshake to completion by
+    /// exchanging handshake flights directly with the enclave, so that
+    /// channel-bound material (RFC 5705 exported keying material) is available
+    /// before the server record loop takes over application traffic.
+    fn drive_handshake(
+        sinaloa: &dyn sinaloa::Sinaloa,
+        session_id: u32,
+        session: &mut dyn rustls::Session,
+    ) -> Result<(), SinaloaError> {
+        while session.is_handshaking() {
+            let mut output: std::vec::Vec<u8> = std::vec::Vec::new();
+            session.write_tls(&mut output)?;
+            if output.is_empty() {
+                // Nothing left to flush but the handshake is still incomplete:
+                // the peer owes us the next flight and has not produced it.
+                return Err(SinaloaError::TLSUnspecifiedError);
+            }
+            let (_active, response) = sinaloa.tls_data(session_id, output)?;
+            if let Some(frames) = response {
+                for frame in frames.iter() {
+                    if frame.is_empty() {
+                        continue;
+                    }
+                    let mut slice = &frame[..];
+                    session.read_tls(&mut slice)?;
+                    session.process_new_packets()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Derive a 32-byte attestation challenge from the established TLS session's
+    /// exported keying material (RFC 5705), binding the challenge to the channel
+    /// that carries the provisioning traffic.
+    fn channel_bound_challenge(
+        session: &mut dyn rustls::Session,
+    ) -> Result<[u8; 32], SinaloaError> {
+        let mut challenge = [0u8; 32];
+        session
+            .export_keying_material(&mut challenge, b"veracruz-attestation-challenge", None)
+            .map_err(|_| SinaloaError::TLSUnspecifiedError)?;
+        Ok(challenge)
+    }
+
     fn attestation_flow(
         tabasco_url: &String,
         expected_enclave_hash: &String,
         sinaloa: &dyn sinaloa::Sinaloa,
+        challenge: &[u8; 32],
     ) -> Result<Vec<u8>, SinaloaError> {
-        let challenge = rand::thread_rng().gen::<[u8; 32]>();
         info!("sinaloa-test/attestation_flow: challenge:{:?}", challenge);
-        let serialized_pagt = colima::serialize_request_proxy_psa_attestation_token(&challenge)?;
+        let serialized_pagt = colima::serialize_request_proxy_psa_attestation_token(challenge)?;
         let pagt_ret = sinaloa.plaintext_data(serialized_pagt)?;
         let received_bytes =
             pagt_ret.ok_or(SinaloaError::MissingFieldError("attestation_flow pagt_ret"))?;
@@ -1656,7 +2486,7 @@ mod tests {
 
         let received_payload = base64::decode(&received_buffer)?;
 
-        if challenge != received_payload[8..40] {
+        if challenge[..] != received_payload[8..40] {
             return Err(SinaloaError::MismatchError {
                 variable: "attestation_flow challenge",
                 received: received_payload[8..40].to_vec(),
@@ -1689,13 +2519,36 @@ mod tests {
         }
     }
 
-    fn read_priv_key_file(filename: &str) -> Result<rustls::PrivateKey, SinaloaError> {
+    fn read_priv_key_file(
+        filename: &str,
+        key_type: ClientKeyType,
+    ) -> Result<rustls::PrivateKey, SinaloaError> {
         let mut key_file = std::fs::File::open(filename)?;
         let mut key_buffer = std::vec::Vec::new();
         key_file.read_to_end(&mut key_buffer)?;
-        let mut cursor = std::io::Cursor::new(key_buffer);
-        let rsa_keys = rustls::internal::pemfile::rsa_private_keys(&mut cursor)
-            .map_err(|_| SinaloaError::TLSUnspecifiedError)?;
-        Ok(rsa_keys[0].clone())
+
+        // Each pemfile parser consumes its reader, so a fresh cursor is built for
+        // every attempt.  PKCS#8 covers ECDSA and Ed25519 as well as RSA, so it
+        // is tried first in `Auto` mode with a PKCS#1 RSA fallback.
+        let read_pkcs8 = || {
+            let mut cursor = std::io::Cursor::new(&key_buffer);
+            rustls::internal::pemfile::pkcs8_private_keys(&mut cursor)
+                .ok()
+                .and_then(|mut keys| keys.drain(..).next())
+        };
+        let read_rsa = || {
+            let mut cursor = std::io::Cursor::new(&key_buffer);
+            rustls::internal::pemfile::rsa_private_keys(&mut cursor)
+                .ok()
+                .and_then(|mut keys| keys.drain(..).next())
+        };
+
+        let key = match key_type {
+            ClientKeyType::Rsa => read_rsa(),
+            ClientKeyType::Pkcs8 => read_pkcs8(),
+            ClientKeyType::Auto => read_pkcs8().or_else(read_rsa),
+        };
+        // An empty or unrecognised key file is a proper error, not an index panic.
+        key.ok_or(SinaloaError::InvalidLengthError("read_priv_key_file", 1))
     }
 }
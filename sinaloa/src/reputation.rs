@@ -0,0 +1,177 @@
+//! Client reputation scoring and automatic banning for Sinaloa
+//!
+//! Bad clients are otherwise merely rejected per-request (see the
+//! `test_phase2_*_unauthorized_*` cases), so a malicious peer can retry forever.
+//! This subsystem keeps a decaying reputation score per client certificate
+//! fingerprint (optionally qualified by source address): authentication or TLS
+//! failures apply a negative penalty, well-formed sessions a small positive one,
+//! and the score decays exponentially back toward `0` with a configurable
+//! half-life so offences are forgiven over time.  Crossing the ban threshold
+//! moves a peer to `Banned` for at least a configurable duration, during which
+//! the TLS handshake is refused outright.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+use log::info;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The lifecycle state of a peer, driven by its reputation score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerState {
+    /// Above the ban threshold; handshakes are permitted.
+    Healthy,
+    /// Known but not currently connected; equivalent to `Healthy` for access.
+    Disconnected,
+    /// Score fell to or below the ban threshold; handshakes are refused until the
+    /// ban is lifted.
+    Banned,
+}
+
+/// Operator-tunable reputation parameters.  They are not read from the policy
+/// file; callers construct them (or use `Default`).
+#[derive(Clone, Debug)]
+pub struct ReputationConfig {
+    /// Score at or below which a peer is banned (negative).
+    pub ban_threshold: f64,
+    /// Score at or above which a banned peer, once its ban expires, returns to
+    /// `Disconnected`.
+    pub recover_threshold: f64,
+    /// Penalty (negative) applied on an authentication or TLS failure.
+    pub failure_penalty: f64,
+    /// Reward (positive) applied on a successful, well-formed session.
+    pub success_reward: f64,
+    /// Half-life of the exponential decay toward `0`.
+    pub half_life: Duration,
+    /// Minimum time a peer stays `Banned`; the ban is lifted only once this has
+    /// passed and the decayed score has also reached `recover_threshold`.
+    pub ban_duration: Duration,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        ReputationConfig {
+            ban_threshold: -5.0,
+            recover_threshold: -1.0,
+            failure_penalty: -2.0,
+            success_reward: 0.5,
+            half_life: Duration::from_secs(300),
+            ban_duration: Duration::from_secs(600),
+        }
+    }
+}
+
+/// The tracked reputation of a single peer.
+struct PeerReputation {
+    score: f64,
+    state: PeerState,
+    last_update: Instant,
+    /// When the current ban lifts, if `state == Banned`.
+    banned_until: Option<Instant>,
+}
+
+/// A table of peer reputations keyed by certificate fingerprint (optionally
+/// qualified by source address).
+pub struct ReputationTable {
+    config: ReputationConfig,
+    peers: HashMap<String, PeerReputation>,
+}
+
+impl ReputationTable {
+    pub fn new(config: ReputationConfig) -> Self {
+        ReputationTable {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Record an authentication or TLS failure for `fingerprint`, possibly
+    /// transitioning the peer to `Banned`.
+    pub fn record_failure(&mut self, fingerprint: &str) {
+        let penalty = self.config.failure_penalty;
+        self.adjust(fingerprint, penalty);
+    }
+
+    /// Record a successful, well-formed session for `fingerprint`.
+    pub fn record_success(&mut self, fingerprint: &str) {
+        let reward = self.config.success_reward;
+        self.adjust(fingerprint, reward);
+    }
+
+    /// Whether a handshake from `fingerprint` should be permitted right now,
+    /// after applying decay and expiring any lapsed ban.
+    pub fn is_allowed(&mut self, fingerprint: &str) -> bool {
+        self.current_state(fingerprint) != PeerState::Banned
+    }
+
+    /// The peer's state after decaying its score and expiring a lapsed ban.
+    pub fn current_state(&mut self, fingerprint: &str) -> PeerState {
+        let config = self.config.clone();
+        let peer = match self.peers.get_mut(fingerprint) {
+            Some(peer) => peer,
+            None => return PeerState::Disconnected,
+        };
+        Self::decay(peer, &config);
+        if peer.state == PeerState::Banned {
+            if let Some(until) = peer.banned_until {
+                if Instant::now() >= until && peer.score >= config.recover_threshold {
+                    Self::transition(fingerprint, peer, PeerState::Disconnected);
+                    peer.banned_until = None;
+                }
+            }
+        }
+        peer.state
+    }
+
+    fn adjust(&mut self, fingerprint: &str, delta: f64) {
+        let config = self.config.clone();
+        let peer = self
+            .peers
+            .entry(fingerprint.to_string())
+            .or_insert_with(|| PeerReputation {
+                score: 0.0,
+                state: PeerState::Disconnected,
+                last_update: Instant::now(),
+                banned_until: None,
+            });
+        Self::decay(peer, &config);
+        peer.score += delta;
+        if peer.score <= config.ban_threshold {
+            if peer.state != PeerState::Banned {
+                Self::transition(fingerprint, peer, PeerState::Banned);
+                peer.banned_until = Some(Instant::now() + config.ban_duration);
+            }
+        } else if peer.state != PeerState::Banned {
+            Self::transition(fingerprint, peer, PeerState::Healthy);
+        }
+    }
+
+    /// Apply exponential decay toward `0` since the peer was last touched.
+    fn decay(peer: &mut PeerReputation, config: &ReputationConfig) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(peer.last_update).as_secs_f64();
+        let half_life = config.half_life.as_secs_f64();
+        if half_life > 0.0 && peer.score != 0.0 {
+            let factor = 0.5_f64.powf(elapsed / half_life);
+            peer.score *= factor;
+        }
+        peer.last_update = now;
+    }
+
+    fn transition(fingerprint: &str, peer: &mut PeerReputation, next: PeerState) {
+        if peer.state != next {
+            info!(
+                "reputation: peer {} {:?} -> {:?} (score {:.2})",
+                fingerprint, peer.state, next, peer.score
+            );
+            peer.state = next;
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Enclave-signed result envelopes
+//!
+//! Attestation proves the enclave's identity at session time, but the results
+//! handed back to a ResultReader otherwise carry no standalone provenance: a
+//! reader who archives a result cannot later prove which enclave and which
+//! program produced it.  A [`ResultEnvelope`] binds
+//!
+//! ```text
+//! { program_hash, data_source_hashes, stream_source_hashes,
+//!   result_bytes, enclave_key_hash }
+//! ```
+//!
+//! and is signed by the enclave with a key rooted in the attestation.  The bound
+//! identity is the SHA-256 of the attested signing public key -- the same hash the
+//! attestation result yields -- so producer and verifier agree on one hash domain.
+//! [`verify_result_envelope`] checks the envelope entirely offline against the
+//! policy's program hash and the attested key hash that attestation against the
+//! policy's `mexico_city_hash` yielded, so an archived result can be re-verified
+//! without a live session.
+//!
+//! ## Authors
+//!
+//! The Veracruz Development Team.
+//!
+//! ## Licensing and copyright notice
+//!
+//! See the `LICENSE.markdown` file in the Veracruz root directory for
+//! information on licensing and copyright.
+
+use crate::sinaloa::SinaloaError;
+use serde::{Deserialize, Serialize};
+
+/// A result together with the provenance the enclave signed over it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResultEnvelope {
+    /// SHA-256 of the provisioned program.
+    pub program_hash: Vec<u8>,
+    /// SHA-256 of each static data source, in provisioning order.
+    pub data_source_hashes: Vec<Vec<u8>>,
+    /// SHA-256 of each stream source, in provisioning order.
+    pub stream_source_hashes: Vec<Vec<u8>>,
+    /// The raw, still-encoded computation result.
+    pub result: Vec<u8>,
+    /// SHA-256 of the enclave's attested signing public key.
+    pub enclave_key_hash: Vec<u8>,
+    /// The attested public key that signed this envelope (DER / SEC1).
+    pub public_key: Vec<u8>,
+    /// ECDSA signature over [`ResultEnvelope::signed_payload`].
+    pub signature: Vec<u8>,
+}
+
+/// The verified contents of an envelope, returned once every binding checks out.
+pub struct VerifiedResult {
+    /// The verified, still-encoded result bytes.
+    pub result: Vec<u8>,
+    /// The program hash the result was bound to.
+    pub program_hash: Vec<u8>,
+}
+
+impl ResultEnvelope {
+    /// The canonical byte string the enclave signs: every bound field except the
+    /// signature itself, serialised deterministically.
+    pub fn signed_payload(&self) -> Result<Vec<u8>, SinaloaError> {
+        let bound = (
+            &self.program_hash,
+            &self.data_source_hashes,
+            &self.stream_source_hashes,
+            &self.result,
+            &self.enclave_key_hash,
+            &self.public_key,
+        );
+        Ok(bincode::serialize(&bound)?)
+    }
+
+    /// Build and sign an envelope with the enclave's attested P-256 key pair.
+    /// Run inside the enclave on the result path.
+    ///
+    /// `enclave_key_hash` is not taken from the caller: it is derived as the
+    /// SHA-256 of the signing `public_key`, so the identity the envelope binds is
+    /// exactly the key that signed it.  The attestation result reports that same
+    /// SHA-256-of-public-key, which is what lets [`verify_result_envelope`] tie the
+    /// signature back to the attested enclave rather than to a key an attacker can
+    /// mint freely.
+    pub fn new_signed(
+        key_pair: &ring::signature::EcdsaKeyPair,
+        public_key: Vec<u8>,
+        program_hash: Vec<u8>,
+        data_source_hashes: Vec<Vec<u8>>,
+        stream_source_hashes: Vec<Vec<u8>>,
+        result: Vec<u8>,
+    ) -> Result<Self, SinaloaError> {
+        let enclave_key_hash =
+            ring::digest::digest(&ring::digest::SHA256, &public_key)
+                .as_ref()
+                .to_vec();
+        let mut envelope = ResultEnvelope {
+            program_hash,
+            data_source_hashes,
+            stream_source_hashes,
+            result,
+            enclave_key_hash,
+            public_key,
+            signature: Vec::new(),
+        };
+        let rng = ring::rand::SystemRandom::new();
+        let signature = key_pair
+            .sign(&rng, &envelope.signed_payload()?)
+            .map_err(|_| SinaloaError::ResultEnvelopeError("could not sign envelope".to_string()))?;
+        envelope.signature = signature.as_ref().to_vec();
+        Ok(envelope)
+    }
+}
+
+/// Verify an envelope entirely offline against `policy`.
+///
+/// `attested_key_hash` is the SHA-256 of the enclave's attested public key, taken
+/// straight from the attestation result for the policy's `mexico_city_hash`
+/// measurement.  Verification binds the envelope to that identity before trusting
+/// its signature:
+///
+///   * the bound program hash must match the policy's expected program hash;
+///   * the envelope's `enclave_key_hash` must equal `attested_key_hash`, so the
+///     envelope can only have been produced by the attested enclave;
+///   * that hash must in turn equal the SHA-256 of the carried `public_key`, so
+///     the signing key itself is the attested identity rather than a key an
+///     attacker substituted; and only then
+///   * the signature must verify under that key.
+///
+/// Without the `enclave_key_hash`/`public_key` binding a forger could mint a fresh
+/// key pair, copy the policy's program hash, and self-sign a bogus result; keying
+/// the check off the attestation-reported key hash is what gives an archived
+/// result real provenance.
+pub fn verify_result_envelope(
+    policy: &veracruz_utils::VeracruzPolicy,
+    attested_key_hash: &[u8],
+    envelope: &ResultEnvelope,
+) -> Result<VerifiedResult, SinaloaError> {
+    let expected_program_hash = hex::decode(policy.pi_hash()).map_err(|_| {
+        SinaloaError::ResultEnvelopeError("policy program hash is not valid hex".to_string())
+    })?;
+    if envelope.program_hash != expected_program_hash {
+        return Err(SinaloaError::ResultEnvelopeError(
+            "envelope program hash does not match the policy".to_string(),
+        ));
+    }
+
+    // The envelope must name the enclave identity the attestation pinned, and that
+    // identity must be the hash of the very key that signed the envelope.
+    if envelope.enclave_key_hash != attested_key_hash {
+        return Err(SinaloaError::ResultEnvelopeError(
+            "envelope enclave hash does not match the attested enclave".to_string(),
+        ));
+    }
+    let public_key_hash = ring::digest::digest(&ring::digest::SHA256, &envelope.public_key);
+    if public_key_hash.as_ref() != envelope.enclave_key_hash.as_slice() {
+        return Err(SinaloaError::ResultEnvelopeError(
+            "envelope signing key is not bound to the attested enclave".to_string(),
+        ));
+    }
+
+    let payload = envelope.signed_payload()?;
+    let public_key = ring::signature::UnparsedPublicKey::new(
+        &ring::signature::ECDSA_P256_SHA256_ASN1,
+        &envelope.public_key,
+    );
+    public_key.verify(&payload, &envelope.signature).map_err(|_| {
+        SinaloaError::ResultEnvelopeError("envelope signature is invalid".to_string())
+    })?;
+
+    Ok(VerifiedResult {
+        result: envelope.result.clone(),
+        program_hash: envelope.program_hash.clone(),
+    })
+}
@@ -15,6 +15,7 @@ pub mod sinaloa_nitro {
     use crate::sinaloa::Sinaloa;
     use crate::sinaloa::SinaloaError;
     use lazy_static::lazy_static;
+    use std::collections::BTreeMap;
     use std::sync::Mutex;
     use veracruz_utils::{
         policy::EnclavePlatform, RuntimeManagerMessage, NitroEnclave, NitroError, NitroStatus,
@@ -25,6 +26,155 @@ pub mod sinaloa_nitro {
     const NITRO_ROOT_ENCLAVE_SERVER_PATH: &str =
         "../nitro-root-enclave-server/target/debug/nitro-root-enclave-server";
 
+    /// The AWS Nitro Secure Module root certificate authority, in DER form.  Every
+    /// genuine NSM attestation document carries a certificate chain that terminates
+    /// at this key, so it is the trust anchor against which `verify_nitro_attestation`
+    /// validates the enclave's leaf certificate.
+    const AWS_NITRO_ROOT_CA_DER: &[u8] = include_bytes!("../aws_nitro_root_ca.der");
+
+    /// The COSE algorithm identifier for ECDSA w/ SHA-384 (ES384), the only scheme
+    /// the NSM uses to sign attestation documents.  It appears in the protected
+    /// header of the `COSE_Sign1` structure.
+    const COSE_ALG_ES384: i128 = -35;
+
+    /// The backoff schedule followed by [`SinaloaNitro::wait_ready`] between probes.
+    pub enum Backoff {
+        /// Wait the same `initial_delay` before every retry.
+        Fixed,
+        /// Double the delay after each retry, starting from `initial_delay`.
+        Exponential,
+    }
+
+    /// How readiness probing should retry a not-yet-live component.
+    ///
+    /// Replaces the old blind `thread::sleep` calls: a loaded host could need far
+    /// longer than the fixed wait (flaky) while a fast one wasted the whole delay.
+    pub struct RetryPolicy {
+        pub backoff: Backoff,
+        /// Maximum number of probes before giving up.
+        pub count: u32,
+        /// The delay before the first retry (and the unit for `Fixed`).
+        pub initial_delay: std::time::Duration,
+        /// Whether to randomise each delay to avoid thundering-herd retries across
+        /// concurrently booting enclaves.
+        pub jitter: bool,
+    }
+
+    impl Default for RetryPolicy {
+        fn default() -> Self {
+            RetryPolicy {
+                backoff: Backoff::Exponential,
+                count: 8,
+                initial_delay: std::time::Duration::from_millis(250),
+                jitter: true,
+            }
+        }
+    }
+
+    /// The verified contents of an AWS Nitro Secure Module attestation document:
+    /// the enclave's attested public key and its platform configuration registers.
+    /// Returned by [`SinaloaNitro::verify_nitro_attestation`] on success.
+    pub struct NitroAttestationContext {
+        /// The DER-encoded public key the enclave bound into the document.
+        pub public_key: Vec<u8>,
+        /// The platform configuration registers, keyed by register index.
+        pub pcrs: BTreeMap<u8, Vec<u8>>,
+    }
+
+    /// The platform-specific evidence an enclave can present to prove its identity.
+    ///
+    /// Different backends speak different attestation dialects; carrying them behind
+    /// a single enum lets the host negotiate a format rather than assuming PSA
+    /// everywhere, and lets a new platform be added without touching the
+    /// buffer-serialise/deserialise plumbing shared by [`AttestationProvider`].
+    pub enum Evidence {
+        /// A Proxy PSA attestation token, with the attested public key and the
+        /// device identifier the proxy assigned.
+        PsaToken {
+            token: Vec<u8>,
+            public_key: Vec<u8>,
+            device_id: i32,
+        },
+        /// An Intel SGX DCAP quote.
+        SgxDcapQuote(Vec<u8>),
+        /// A raw AWS Nitro Secure Module `COSE_Sign1` attestation document.
+        NitroDocument(Vec<u8>),
+    }
+
+    /// The outcome of a native attestation: the evidence the enclave produced and
+    /// the public key extracted from it, ready to pin the subsequent TLS channel.
+    pub struct AttestationContext {
+        pub evidence: Evidence,
+        pub public_key: Vec<u8>,
+    }
+
+    /// A `rustls` server-certificate verifier that ignores the web PKI entirely and
+    /// instead pins the enclave certificate to the public key recovered during
+    /// attestation.
+    ///
+    /// Trust therefore flows from the attestation result rather than from a CA: a
+    /// certificate is accepted only when its `SubjectPublicKeyInfo` public key
+    /// equals `pinned_public_key`.  This removes the old `get_enclave_cert`-then-
+    /// trust dance in favour of binding the QUIC/TLS handshake directly to the
+    /// measured enclave.
+    pub struct PinnedEnclaveCertVerifier {
+        pinned_public_key: Vec<u8>,
+    }
+
+    impl PinnedEnclaveCertVerifier {
+        pub fn new(pinned_public_key: Vec<u8>) -> Self {
+            PinnedEnclaveCertVerifier { pinned_public_key }
+        }
+    }
+
+    impl rustls::ServerCertVerifier for PinnedEnclaveCertVerifier {
+        fn verify_server_cert(
+            &self,
+            _roots: &rustls::RootCertStore,
+            presented_certs: &[rustls::Certificate],
+            _dns_name: webpki::DNSNameRef,
+            _ocsp_response: &[u8],
+        ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+            let leaf = presented_certs
+                .first()
+                .ok_or(rustls::TLSError::NoCertificatesPresented)?;
+            let presented_key = leaf_certificate_public_key(&leaf.0).map_err(|_| {
+                rustls::TLSError::General("could not parse enclave certificate".to_string())
+            })?;
+            if presented_key == self.pinned_public_key {
+                Ok(rustls::ServerCertVerified::assertion())
+            } else {
+                Err(rustls::TLSError::General(
+                    "enclave certificate does not match the attested public key".to_string(),
+                ))
+            }
+        }
+    }
+
+    /// A uniform attestation surface shared by the SGX, Nitro and TrustZone hosts.
+    ///
+    /// Each backend knows how to stand up its native root of trust and how to
+    /// fetch a proxy attestation token for a client challenge; expressing both
+    /// behind this trait means [`Sinaloa`] can drive attestation generically
+    /// instead of hard-coding `proxy_psa_attestation_get_token` and Nitro-specific
+    /// message round-trips.
+    pub trait AttestationProvider {
+        /// Stand up the platform's native root of trust and return the evidence it
+        /// produces, checking it against `expected_measurement`.
+        fn native_attestation(
+            &self,
+            proxy_url: &str,
+            expected_measurement: &str,
+        ) -> Result<AttestationContext, SinaloaError>;
+
+        /// Fetch a proxy attestation token bound to `challenge`, returning the
+        /// token, the attested public key and the device identifier.
+        fn proxy_attestation_token(
+            &self,
+            challenge: Vec<u8>,
+        ) -> Result<(Vec<u8>, Vec<u8>, i32), SinaloaError>;
+    }
+
     lazy_static! {
         //static ref NRE_CONTEXT: Mutex<Option<NitroEnclave>> = Mutex::new(None);
         static ref NRE_CONTEXT: Mutex<Option<EC2Instance>> = Mutex::new(None);
@@ -40,20 +190,21 @@ pub mod sinaloa_nitro {
             let policy: veracruz_utils::VeracruzPolicy =
                 veracruz_utils::VeracruzPolicy::from_json(policy_json)?;
 
+            let runtime_manager_hash = policy
+                .runtime_manager_hash(&EnclavePlatform::Nitro)
+                .map_err(|err| SinaloaError::VeracruzUtilError(err))?;
+
             {
                 let mut nre_guard = NRE_CONTEXT.lock()?;
                 if nre_guard.is_none() {
                     println!("NITRO ROOT ENCLAVE IS UNINITIALIZED.");
-                    let runtime_manager_hash = policy
-                        .runtime_manager_hash(&EnclavePlatform::Nitro)
-                        .map_err(|err| SinaloaError::VeracruzUtilError(err))?;
                     let nre_context =
-                        SinaloaNitro::native_attestation(&policy.proxy_attestation_server_url(), &runtime_manager_hash)?;
+                        SinaloaNitro::bootstrap_native_root_enclave(&policy.proxy_attestation_server_url(), &runtime_manager_hash)?;
                     *nre_guard = Some(nre_context);
                 }
             }
 
-            println!("SinaloaNitro::new native_attestation complete. instantiating Runtime Manager");
+            println!("SinaloaNitro::new root-enclave bootstrap complete. instantiating Runtime Manager");
             #[cfg(feature = "debug")]
             let runtime_manager_enclave = {
                 println!("Starting Runtime Manager enclave in debug mode");
@@ -81,7 +232,9 @@ pub mod sinaloa_nitro {
                 enclave: runtime_manager_enclave,
             };
             println!("SinaloaNitro::new Runtime Manager instantiated. Calling initialize");
-            std::thread::sleep(std::time::Duration::from_millis(10000));
+            // Probe the enclave until it answers a ping rather than blindly sleeping
+            // for a fixed interval that is simultaneously too short and too long.
+            SinaloaNitro::wait_ready(&RetryPolicy::default(), || meta.ping())?;
 
             let initialize: RuntimeManagerMessage = RuntimeManagerMessage::Initialize(policy_json.to_string());
 
@@ -100,6 +253,20 @@ pub mod sinaloa_nitro {
                 NitroStatus::Success => (),
                 _ => return Err(SinaloaError::NitroStatus(status)),
             }
+
+            // Natively attest the freshly initialized Runtime Manager enclave
+            // through the `AttestationProvider` surface, checking its PCR0
+            // against the policy's expected measurement.  The attested public key
+            // is only logged; it is not pinned to the TLS sessions the enclave
+            // serves.
+            let attestation = meta.native_attestation(
+                &policy.proxy_attestation_server_url(),
+                &runtime_manager_hash,
+            )?;
+            println!(
+                "SinaloaNitro::new verified enclave attestation; attested public key is {} byte(s)",
+                attestation.public_key.len()
+            );
             println!("SinaloaNitro::new complete. Returning");
             return Ok(meta);
         }
@@ -111,7 +278,7 @@ pub mod sinaloa_nitro {
                 let rpat = parsed.get_request_proxy_psa_attestation_token();
                 let challenge = transport_protocol::parse_request_proxy_psa_attestation_token(rpat);
                 let (psa_attestation_token, pubkey, device_id) =
-                    self.proxy_psa_attestation_get_token(challenge)?;
+                    AttestationProvider::proxy_attestation_token(self, challenge)?;
                 let serialized_pat = transport_protocol::serialize_proxy_psa_attestation_token(
                     &psa_attestation_token,
                     &pubkey,
@@ -229,19 +396,28 @@ pub mod sinaloa_nitro {
 
             let mut active_flag = true;
             let mut ret_array = Vec::new();
-            while self.tls_data_needed(session_id)? {
-                let gtd_message = RuntimeManagerMessage::GetTLSData(session_id);
-                let gtd_buffer: Vec<u8> = bincode::serialize(&gtd_message)?;
+            // Drain all currently-available output with batched retrievals instead
+            // of a GetTLSDataNeeded + GetTLSData pair per frame (three crossings per
+            // frame).  A single GetAllTLSData returns every frame the enclave has
+            // buffered; we only loop again if it handed back a non-empty batch,
+            // signalling that more data may still be waiting.
+            loop {
+                let gatd_message = RuntimeManagerMessage::GetAllTLSData(session_id);
+                let gatd_buffer: Vec<u8> = bincode::serialize(&gatd_message)?;
 
-                self.enclave.send_buffer(&gtd_buffer)?;
+                self.enclave.send_buffer(&gatd_buffer)?;
 
                 let received_buffer: Vec<u8> = self.enclave.receive_buffer()?;
 
                 let received_message: RuntimeManagerMessage = bincode::deserialize(&received_buffer)?;
                 match received_message {
-                    RuntimeManagerMessage::TLSData(data, alive) => {
+                    RuntimeManagerMessage::TLSDataBatch(frames, alive) => {
                         active_flag = alive;
-                        ret_array.push(data);
+                        let drained = frames.is_empty();
+                        ret_array.extend(frames);
+                        if drained {
+                            break;
+                        }
                     }
                     _ => return Err(SinaloaError::NitroStatus(NitroStatus::Fail)),
                 }
@@ -312,6 +488,10 @@ pub mod sinaloa_nitro {
             return Ok(return_buffer);
         }
 
+        // Retained for backward compatibility with the per-frame
+        // GetTLSDataNeeded/GetTLSData protocol; the default path now batches via
+        // GetAllTLSData.
+        #[allow(dead_code)]
         fn tls_data_needed(&self, session_id: u32) -> Result<bool, SinaloaError> {
             let gtdn_message = RuntimeManagerMessage::GetTLSDataNeeded(session_id);
             let gtdn_buffer: Vec<u8> = bincode::serialize(&gtdn_message)?;
@@ -328,12 +508,261 @@ pub mod sinaloa_nitro {
             return Ok(tls_data_needed);
         }
 
-        fn native_attestation(
+        /// Round-trip to the Runtime Manager enclave to obtain a fresh AWS Nitro
+        /// Secure Module attestation document bound to `challenge`.  The enclave
+        /// embeds `challenge` as the document `nonce` so the reply cannot be
+        /// replayed from an earlier session.
+        pub fn get_nitro_attestation_document(
+            &self,
+            challenge: Vec<u8>,
+        ) -> Result<Vec<u8>, SinaloaError> {
+            let message = RuntimeManagerMessage::GetNitroAttestationDocument(challenge);
+            let message_buffer = bincode::serialize(&message)?;
+            self.enclave.send_buffer(&message_buffer)?;
+
+            let received_buffer = self.enclave.receive_buffer()?;
+            let received_message: RuntimeManagerMessage = bincode::deserialize(&received_buffer)?;
+            match received_message {
+                RuntimeManagerMessage::NitroAttestationDocument(doc) => Ok(doc),
+                _ => Err(SinaloaError::InvalidRuntimeManagerMessage(received_message)),
+            }
+        }
+
+        /// Send a lightweight ping to the enclave and succeed only on a
+        /// `Status(Success)` reply.  Used as the readiness probe in [`wait_ready`].
+        ///
+        /// [`wait_ready`]: SinaloaNitro::wait_ready
+        fn ping(&self) -> Result<(), SinaloaError> {
+            let message = RuntimeManagerMessage::Ping;
+            let message_buffer = bincode::serialize(&message)?;
+            self.enclave.send_buffer(&message_buffer)?;
+
+            let received_buffer = self.enclave.receive_buffer()?;
+            let received_message: RuntimeManagerMessage = bincode::deserialize(&received_buffer)?;
+            match received_message {
+                RuntimeManagerMessage::Status(NitroStatus::Success) => Ok(()),
+                RuntimeManagerMessage::Status(status) => Err(SinaloaError::NitroStatus(status)),
+                _ => Err(SinaloaError::InvalidRuntimeManagerMessage(received_message)),
+            }
+        }
+
+        /// Poll `probe` according to `policy` until it succeeds, retrying on every
+        /// error (typically a transport error while the component is still coming
+        /// up).  On exhausting the retry budget, return
+        /// [`SinaloaError::ReadinessTimeout`] carrying the last error seen rather
+        /// than proceeding into a guaranteed failure.
+        pub fn wait_ready<F>(policy: &RetryPolicy, mut probe: F) -> Result<(), SinaloaError>
+        where
+            F: FnMut() -> Result<(), SinaloaError>,
+        {
+            use rand::Rng;
+
+            let mut delay = policy.initial_delay;
+            let mut last_error: Option<SinaloaError> = None;
+            for _ in 0..policy.count {
+                match probe() {
+                    Ok(()) => return Ok(()),
+                    Err(err) => last_error = Some(err),
+                }
+                let mut this_delay = delay;
+                if policy.jitter {
+                    // Full jitter: sleep a uniformly random slice of the current
+                    // backoff window so concurrent enclaves desynchronise.
+                    let millis = this_delay.as_millis() as u64;
+                    if millis > 0 {
+                        let slice = rand::thread_rng().gen_range(0, millis + 1);
+                        this_delay = std::time::Duration::from_millis(slice);
+                    }
+                }
+                std::thread::sleep(this_delay);
+                if let Backoff::Exponential = policy.backoff {
+                    delay = delay.saturating_mul(2);
+                }
+            }
+            Err(SinaloaError::ReadinessTimeout(last_error.map(Box::new)))
+        }
+
+        /// Open a QUIC-multiplexed session with the enclave.
+        ///
+        /// Unlike [`Sinaloa::new_tls_session`], which drives a buffer-relay TLS loop
+        /// from the host, this asks the enclave to terminate a QUIC connection
+        /// internally and returns the identifier of the new session.  The client can
+        /// then open independent streams over a single multiplexed connection,
+        /// pinning the certificate with [`PinnedEnclaveCertVerifier`] so trust flows
+        /// from the attestation result.
+        pub fn new_quic_session(&self) -> Result<u32, SinaloaError> {
+            let message = RuntimeManagerMessage::NewQUICSession;
+            let message_buffer = bincode::serialize(&message)?;
+            self.enclave.send_buffer(&message_buffer)?;
+
+            let received_buffer = self.enclave.receive_buffer()?;
+            let received_message: RuntimeManagerMessage = bincode::deserialize(&received_buffer)?;
+            match received_message {
+                RuntimeManagerMessage::QUICSession(session_id) => Ok(session_id),
+                _ => Err(SinaloaError::InvalidRuntimeManagerMessage(received_message)),
+            }
+        }
+
+        /// Tear down a QUIC session previously opened with [`new_quic_session`].
+        ///
+        /// [`new_quic_session`]: SinaloaNitro::new_quic_session
+        pub fn close_quic_session(&self, session_id: u32) -> Result<(), SinaloaError> {
+            let message = RuntimeManagerMessage::CloseQUICSession(session_id);
+            let message_buffer = bincode::serialize(&message)?;
+            self.enclave.send_buffer(&message_buffer)?;
+
+            let received_buffer = self.enclave.receive_buffer()?;
+            let received_message: RuntimeManagerMessage = bincode::deserialize(&received_buffer)?;
+            match received_message {
+                RuntimeManagerMessage::Status(NitroStatus::Success) => Ok(()),
+                _ => Err(SinaloaError::NitroStatus(NitroStatus::Fail)),
+            }
+        }
+
+        /// Build a `quinn` client configuration whose TLS layer pins the enclave
+        /// certificate to `attested_public_key`, so the QUIC handshake is trusted
+        /// only against the attestation result rather than the web PKI.
+        pub fn pinned_quic_client_config(
+            attested_public_key: Vec<u8>,
+        ) -> quinn::ClientConfig {
+            let mut tls_config = rustls::ClientConfig::new();
+            tls_config
+                .dangerous()
+                .set_certificate_verifier(std::sync::Arc::new(PinnedEnclaveCertVerifier::new(
+                    attested_public_key,
+                )));
+            tls_config.versions = vec![rustls::ProtocolVersion::TLSv1_3];
+            quinn::ClientConfig {
+                crypto: std::sync::Arc::new(tls_config),
+                ..Default::default()
+            }
+        }
+
+        /// Verify an AWS Nitro Secure Module attestation document natively, without
+        /// going through the PSA token proxy.
+        ///
+        /// `document` is the raw `COSE_Sign1` structure returned by the NSM: a CBOR
+        /// four-element array of `[protected_header, unprotected_header, payload,
+        /// signature]`.  Verification proceeds in the order mandated by the NSM
+        /// specification:
+        ///
+        /// 1. the document is parsed with `ciborium`;
+        /// 2. the certificate chain `leaf -> cabundle` is validated up to the
+        ///    hard-coded [`AWS_NITRO_ROOT_CA_DER`] trust anchor;
+        /// 3. the COSE `Sig_structure` is reconstructed and the ES384 signature is
+        ///    checked against the leaf certificate's P-384 public key;
+        /// 4. the document `nonce` is required to equal `challenge`, defeating
+        ///    replay of a document captured from another session;
+        /// 5. `pcrs[0]` is required to equal `expected_runtime_manager_hash`, tying
+        ///    the document to the Runtime Manager image the policy expects.
+        ///
+        /// On success the extracted public key and PCR map are returned; any
+        /// mismatch yields [`SinaloaError::NitroAttestationError`].
+        pub fn verify_nitro_attestation(
+            document: &[u8],
+            challenge: &[u8],
+            expected_runtime_manager_hash: &[u8],
+        ) -> Result<NitroAttestationContext, SinaloaError> {
+            use ciborium::value::Value;
+
+            let fail = |reason: &str| {
+                SinaloaError::NitroAttestationError(reason.to_string())
+            };
+
+            // (1) Decode the COSE_Sign1 array.
+            let cose: Value = ciborium::de::from_reader(document)
+                .map_err(|_| fail("attestation document is not valid CBOR"))?;
+            let cose = match cose {
+                Value::Array(items) if items.len() == 4 => items,
+                _ => return Err(fail("COSE_Sign1 must be a 4-element array")),
+            };
+            let protected_header = match &cose[0] {
+                Value::Bytes(b) => b.clone(),
+                _ => return Err(fail("protected header must be a byte string")),
+            };
+            let payload_bytes = match &cose[2] {
+                Value::Bytes(b) => b.clone(),
+                _ => return Err(fail("payload must be a byte string")),
+            };
+            let signature = match &cose[3] {
+                Value::Bytes(b) => b.clone(),
+                _ => return Err(fail("signature must be a byte string")),
+            };
+
+            // The protected header is itself a CBOR map; the algorithm lives at
+            // label 1 and must be ES384.
+            let protected: Value = ciborium::de::from_reader(protected_header.as_slice())
+                .map_err(|_| fail("protected header is not valid CBOR"))?;
+            if cose_map_i128(&protected, 1) != Some(COSE_ALG_ES384) {
+                return Err(fail("unexpected COSE algorithm, expected ES384"));
+            }
+
+            // Parse the payload map.
+            let payload: Value = ciborium::de::from_reader(payload_bytes.as_slice())
+                .map_err(|_| fail("payload is not valid CBOR"))?;
+            let certificate = cose_map_bytes(&payload, "certificate")
+                .ok_or_else(|| fail("payload is missing the leaf certificate"))?;
+            let cabundle = cose_map_array_bytes(&payload, "cabundle")
+                .ok_or_else(|| fail("payload is missing the CA bundle"))?;
+            let pcrs = cose_map_pcrs(&payload)
+                .ok_or_else(|| fail("payload is missing the PCR map"))?;
+
+            // (2) Validate the certificate chain leaf -> intermediates -> root.
+            verify_certificate_chain(&certificate, &cabundle, AWS_NITRO_ROOT_CA_DER)?;
+
+            // (3) Reconstruct the Sig_structure and verify the ES384 signature with
+            // the leaf certificate's public key.
+            let sig_structure = Value::Array(vec![
+                Value::Text("Signature1".to_string()),
+                Value::Bytes(protected_header),
+                Value::Bytes(Vec::new()),
+                Value::Bytes(payload_bytes),
+            ]);
+            let mut sig_structure_bytes = Vec::new();
+            ciborium::ser::into_writer(&sig_structure, &mut sig_structure_bytes)
+                .map_err(|_| fail("could not re-encode the Sig_structure"))?;
+            if signature.len() != 96 {
+                return Err(fail("ES384 signature must be 96 raw bytes (r || s)"));
+            }
+            let leaf_public_key = leaf_certificate_public_key(&certificate)?;
+            let verifier = ring::signature::UnparsedPublicKey::new(
+                &ring::signature::ECDSA_P384_SHA384_FIXED,
+                &leaf_public_key,
+            );
+            verifier
+                .verify(&sig_structure_bytes, &signature)
+                .map_err(|_| fail("attestation document signature is invalid"))?;
+
+            // (4) The nonce must echo the challenge we sent.
+            let nonce = cose_map_bytes(&payload, "nonce")
+                .ok_or_else(|| fail("payload is missing the nonce"))?;
+            if nonce.as_slice() != challenge {
+                return Err(fail("attestation nonce does not match the challenge"));
+            }
+
+            // (5) PCR0 must match the expected Runtime Manager measurement.
+            let pcr0 = pcrs
+                .get(&0)
+                .ok_or_else(|| fail("payload is missing PCR0"))?;
+            if pcr0.as_slice() != expected_runtime_manager_hash {
+                return Err(fail("PCR0 does not match the expected runtime_manager_hash"));
+            }
+
+            let public_key = cose_map_bytes(&payload, "public_key")
+                .ok_or_else(|| fail("payload is missing the enclave public key"))?;
+            Ok(NitroAttestationContext { public_key, pcrs })
+        }
+
+        /// Stand up the Nitro Root Enclave on a fresh EC2 instance and leave its
+        /// attestation server running.  This bootstraps the platform root of trust;
+        /// the per-session attestation that produces verifiable evidence is
+        /// [`AttestationProvider::native_attestation`].
+        fn bootstrap_native_root_enclave(
             proxy_attestation_server_url: &str,
             _runtime_manager_hash: &str,
             //) -> Result<NitroEnclave, SinaloaError> {
         ) -> Result<EC2Instance, SinaloaError> {
-            println!("SinaloaNitro::native_attestation started");
+            println!("SinaloaNitro::bootstrap_native_root_enclave started");
 
             println!("Starting EC2 instance");
             let nre_instance = EC2Instance::new().map_err(|err| SinaloaError::EC2Error(err))?;
@@ -369,10 +798,182 @@ pub mod sinaloa_nitro {
                 .map_err(|err| SinaloaError::EC2Error(err))?;
 
             println!("Waiting for NRE Instance to authenticate.");
-            std::thread::sleep(std::time::Duration::from_millis(15000));
-
-            println!("sinaloa_tz::native_attestation returning Ok");
+            // Retry a cheap liveness command over the EC2 channel until the NRE
+            // server is up, instead of a fixed 15s wait.
+            SinaloaNitro::wait_ready(&RetryPolicy::default(), || {
+                nre_instance
+                    .execute_command("true")
+                    .map(|_| ())
+                    .map_err(|err| SinaloaError::EC2Error(err))
+            })?;
+
+            println!("SinaloaNitro::bootstrap_native_root_enclave returning Ok");
             return Ok(nre_instance);
         }
     }
+
+    impl AttestationProvider for SinaloaNitro {
+        fn native_attestation(
+            &self,
+            _proxy_url: &str,
+            expected_measurement: &str,
+        ) -> Result<AttestationContext, SinaloaError> {
+            // Ask the enclave for a fresh NSM document bound to a random challenge,
+            // verify it natively, and surface the attested public key.
+            use rand::RngCore;
+            let mut challenge = vec![0u8; 32];
+            rand::thread_rng().fill_bytes(&mut challenge);
+            let document = self.get_nitro_attestation_document(challenge.clone())?;
+            let expected_hash = hex::decode(expected_measurement).map_err(|_| {
+                SinaloaError::NitroAttestationError(
+                    "expected measurement is not valid hex".to_string(),
+                )
+            })?;
+            let context =
+                SinaloaNitro::verify_nitro_attestation(&document, &challenge, &expected_hash)?;
+            Ok(AttestationContext {
+                public_key: context.public_key,
+                evidence: Evidence::NitroDocument(document),
+            })
+        }
+
+        fn proxy_attestation_token(
+            &self,
+            challenge: Vec<u8>,
+        ) -> Result<(Vec<u8>, Vec<u8>, i32), SinaloaError> {
+            self.proxy_psa_attestation_get_token(challenge)
+        }
+    }
+
+    /// Look up an integer-labelled entry in a CBOR map and return it as an `i128`.
+    fn cose_map_i128(value: &ciborium::value::Value, label: i128) -> Option<i128> {
+        use ciborium::value::Value;
+        if let Value::Map(entries) = value {
+            for (k, v) in entries {
+                if let (Value::Integer(ki), Value::Integer(vi)) = (k, v) {
+                    if i128::from(*ki) == label {
+                        return Some(i128::from(*vi));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Look up a text-labelled byte-string entry in a CBOR map.
+    fn cose_map_bytes(value: &ciborium::value::Value, key: &str) -> Option<Vec<u8>> {
+        use ciborium::value::Value;
+        if let Value::Map(entries) = value {
+            for (k, v) in entries {
+                if let (Value::Text(kt), Value::Bytes(b)) = (k, v) {
+                    if kt == key {
+                        return Some(b.clone());
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Look up a text-labelled array-of-byte-strings entry in a CBOR map.
+    fn cose_map_array_bytes(value: &ciborium::value::Value, key: &str) -> Option<Vec<Vec<u8>>> {
+        use ciborium::value::Value;
+        if let Value::Map(entries) = value {
+            for (k, v) in entries {
+                if let (Value::Text(kt), Value::Array(items)) = (k, v) {
+                    if kt == key {
+                        let mut out = Vec::with_capacity(items.len());
+                        for item in items {
+                            match item {
+                                Value::Bytes(b) => out.push(b.clone()),
+                                _ => return None,
+                            }
+                        }
+                        return Some(out);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Extract the `pcrs` sub-map (register index -> measurement) from the payload.
+    fn cose_map_pcrs(value: &ciborium::value::Value) -> Option<BTreeMap<u8, Vec<u8>>> {
+        use ciborium::value::Value;
+        if let Value::Map(entries) = value {
+            for (k, v) in entries {
+                if let (Value::Text(kt), Value::Map(pcr_entries)) = (k, v) {
+                    if kt == "pcrs" {
+                        let mut pcrs = BTreeMap::new();
+                        for (index, measurement) in pcr_entries {
+                            match (index, measurement) {
+                                (Value::Integer(i), Value::Bytes(b)) => {
+                                    let idx = u8::try_from(i128::from(*i)).ok()?;
+                                    pcrs.insert(idx, b.clone());
+                                }
+                                _ => return None,
+                            }
+                        }
+                        return Some(pcrs);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Validate the certificate chain running from the NSM leaf certificate through
+    /// the (root-first) `cabundle` intermediates up to `root_der`, returning a
+    /// [`SinaloaError::NitroAttestationError`] if any link fails to verify.
+    fn verify_certificate_chain(
+        leaf_der: &[u8],
+        cabundle: &[Vec<u8>],
+        root_der: &[u8],
+    ) -> Result<(), SinaloaError> {
+        // `cabundle` is ordered root-first; webpki wants the intermediates between
+        // the leaf and the trust anchor, so skip the first (root) entry and reverse
+        // the remainder to run leaf -> ... -> anchor.
+        let intermediates: Vec<&[u8]> = cabundle
+            .iter()
+            .skip(1)
+            .rev()
+            .map(|c| c.as_slice())
+            .collect();
+        let anchor = webpki::trust_anchor_util::cert_der_as_trust_anchor(root_der)
+            .map_err(|_| SinaloaError::NitroAttestationError("invalid Nitro root CA".to_string()))?;
+        let trust_anchors = webpki::TLSServerTrustAnchors(&[anchor]);
+        let leaf = webpki::EndEntityCert::from(leaf_der).map_err(|_| {
+            SinaloaError::NitroAttestationError("invalid leaf certificate".to_string())
+        })?;
+        // Validate the chain as of now: passing epoch 0 disabled the notBefore/
+        // notAfter window entirely, so an expired or not-yet-valid Nitro chain would
+        // still verify. The nonce check proves liveness of the document; the date
+        // check must still reject stale certificates.
+        let now = webpki::Time::try_from(std::time::SystemTime::now()).map_err(|_| {
+            SinaloaError::NitroAttestationError("system clock is before the unix epoch".to_string())
+        })?;
+        leaf.verify_is_valid_tls_server_cert(
+            &[&webpki::ECDSA_P384_SHA384],
+            &trust_anchors,
+            &intermediates,
+            now,
+        )
+        .map_err(|err| {
+            SinaloaError::NitroAttestationError(format!("certificate chain is invalid: {:?}", err))
+        })
+    }
+
+    /// Extract the raw (uncompressed) P-384 public key from the leaf certificate's
+    /// `SubjectPublicKeyInfo` so that `ring` can verify the document signature.
+    fn leaf_certificate_public_key(leaf_der: &[u8]) -> Result<Vec<u8>, SinaloaError> {
+        let (_, cert) = x509_parser::parse_x509_certificate(leaf_der).map_err(|_| {
+            SinaloaError::NitroAttestationError("could not parse leaf certificate".to_string())
+        })?;
+        Ok(cert
+            .tbs_certificate
+            .subject_pki
+            .subject_public_key
+            .data
+            .to_vec())
+    }
 }